@@ -0,0 +1,223 @@
+use std::io::IsTerminal;
+
+use crate::config::CompareArgs;
+use crate::error::{RegressionError, Result};
+use crate::report::{LatencyReport, RunReport};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Metric values of one scope, either the whole run or a single endpoint
+struct ScopeMetrics {
+    latency: LatencyReport,
+    error_rate: f64,
+    rps: f64,
+}
+
+impl ScopeMetrics {
+    fn new(latency: &LatencyReport, count: u64, errors: u64, rps: f64) -> ScopeMetrics {
+        let error_rate = if count == 0 {
+            0.0
+        } else {
+            errors as f64 * 100.0 / count as f64
+        };
+        ScopeMetrics {
+            latency: latency.clone(),
+            error_rate,
+            rps,
+        }
+    }
+
+    fn from_total(report: &RunReport) -> ScopeMetrics {
+        ScopeMetrics::new(&report.latency, report.total, report.failed, report.rps)
+    }
+
+    fn from_endpoint(report: &RunReport, name: &str) -> Option<ScopeMetrics> {
+        report
+            .endpoints
+            .iter()
+            .find(|endpoint| endpoint.name == name)
+            .map(|endpoint| {
+                ScopeMetrics::new(
+                    &endpoint.latency,
+                    endpoint.count,
+                    endpoint.errors,
+                    endpoint.rps,
+                )
+            })
+    }
+}
+
+enum Verdict {
+    Regression,
+    Improvement,
+    Unchanged,
+}
+
+/// Compares two saved JSON reports, failing when the current run regressed
+pub fn compare(args: &CompareArgs) -> Result<()> {
+    let baseline = RunReport::load(args.baseline.as_path())?;
+    let current = RunReport::load(args.current.as_path())?;
+
+    println!("Baseline: {} ({})", baseline.command, baseline.started_at);
+    println!("Current: {} ({})", current.command, current.started_at);
+    println!(
+        "Tolerance: {}% latency and throughput, {} points error rate",
+        args.tolerance, args.error_tolerance
+    );
+    println!();
+    println!(
+        "{:<16} {:<11} {:>12} {:>12} {:>10}",
+        "Endpoint", "Metric", "Baseline", "Current", "Delta"
+    );
+
+    let colored = std::io::stdout().is_terminal();
+    let mut regressions: usize = 0;
+
+    let mut scopes: Vec<(String, Option<ScopeMetrics>, Option<ScopeMetrics>)> = vec![(
+        String::from("total"),
+        Some(ScopeMetrics::from_total(&baseline)),
+        Some(ScopeMetrics::from_total(&current)),
+    )];
+
+    let mut names: Vec<&str> = baseline
+        .endpoints
+        .iter()
+        .chain(current.endpoints.iter())
+        .map(|endpoint| endpoint.name.as_str())
+        .collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        scopes.push((
+            name.to_string(),
+            ScopeMetrics::from_endpoint(&baseline, name),
+            ScopeMetrics::from_endpoint(&current, name),
+        ));
+    }
+
+    for (name, base, cur) in scopes {
+        let (Some(base), Some(cur)) = (base, cur) else {
+            println!("{:<16} only present in one of the runs", name);
+            continue;
+        };
+
+        let latencies = [
+            ("p50_ms", base.latency.p50_ms, cur.latency.p50_ms),
+            ("p90_ms", base.latency.p90_ms, cur.latency.p90_ms),
+            ("p95_ms", base.latency.p95_ms, cur.latency.p95_ms),
+            ("p99_ms", base.latency.p99_ms, cur.latency.p99_ms),
+        ];
+
+        let mut rows: Vec<(&str, f64, f64, String, Verdict)> = Vec::new();
+        for (metric, base_value, cur_value) in latencies {
+            let delta = relative_delta(base_value, cur_value);
+            let verdict = judge(delta, args.tolerance);
+            rows.push((
+                metric,
+                base_value,
+                cur_value,
+                format_relative(delta),
+                verdict,
+            ));
+        }
+
+        let error_delta = cur.error_rate - base.error_rate;
+        rows.push((
+            "error_rate",
+            base.error_rate,
+            cur.error_rate,
+            format!("{:+.2} pts", error_delta),
+            judge(error_delta, args.error_tolerance),
+        ));
+
+        // Lower throughput is worse, flip the sign so regressions stay positive
+        let rps_delta = relative_delta(base.rps, cur.rps);
+        rows.push((
+            "rps",
+            base.rps,
+            cur.rps,
+            format_relative(rps_delta),
+            judge(-rps_delta, args.tolerance),
+        ));
+
+        for (metric, base_value, cur_value, delta, verdict) in rows {
+            let line = format!(
+                "{:<16} {:<11} {:>12.3} {:>12.3} {:>10}",
+                name, metric, base_value, cur_value, delta
+            );
+            match verdict {
+                Verdict::Regression => {
+                    regressions += 1;
+                    if colored {
+                        println!("{}{}  REGRESSION{}", RED, line, RESET);
+                    } else {
+                        println!("{}  REGRESSION", line);
+                    }
+                }
+                Verdict::Improvement if colored => println!("{}{}{}", GREEN, line, RESET),
+                _ => println!("{}", line),
+            }
+        }
+    }
+
+    if regressions > 0 {
+        return Err(RegressionError { regressions }.into());
+    }
+
+    println!();
+    println!("No regressions found");
+
+    Ok(())
+}
+
+/// Change in percent relative to the baseline
+fn relative_delta(base: f64, current: f64) -> f64 {
+    if base == 0.0 {
+        if current == 0.0 {
+            return 0.0;
+        }
+        return 100.0 * current.signum();
+    }
+    (current - base) * 100.0 / base
+}
+
+/// Positive deltas are worse than the baseline
+fn judge(delta: f64, tolerance: f64) -> Verdict {
+    if delta > tolerance {
+        Verdict::Regression
+    } else if delta < -tolerance {
+        Verdict::Improvement
+    } else {
+        Verdict::Unchanged
+    }
+}
+
+fn format_relative(delta: f64) -> String {
+    format!("{:+.2}%", delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_delta_is_a_percentage_of_the_baseline() {
+        assert_eq!(relative_delta(100.0, 150.0), 50.0);
+        assert_eq!(relative_delta(200.0, 100.0), -50.0);
+        assert_eq!(relative_delta(42.0, 42.0), 0.0);
+    }
+
+    #[test]
+    fn relative_delta_from_a_zero_baseline() {
+        assert_eq!(relative_delta(0.0, 0.0), 0.0);
+        assert_eq!(relative_delta(0.0, 3.0), 100.0);
+        assert_eq!(relative_delta(0.0, -3.0), -100.0);
+    }
+
+    #[test]
+    fn relative_delta_to_zero() {
+        assert_eq!(relative_delta(10.0, 0.0), -100.0);
+    }
+}
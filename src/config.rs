@@ -1,15 +1,145 @@
-use clap::{Parser, Subcommand};
-use serde::Deserialize;
+use clap::{Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
 use std::path::Path;
+use std::time::Duration;
 use std::{fs, path::PathBuf};
 
-#[derive(Clone, Deserialize, Debug)]
+use crate::report::ReportFormat;
+use crate::retry::RetryPolicy;
+use crate::thresholds::Thresholds;
+
+/// Issue types the API accepts
+const ISSUE_TYPES: [&str; 8] = [
+    "initiative",
+    "epic",
+    "user_story",
+    "task",
+    "issue",
+    "feature",
+    "bug",
+    "test_case",
+];
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct Config {
+    #[serde(skip_serializing)]
     pub token: String,
     pub base_url: String,
     pub project_id: String,
     pub issue_count: u32,
     pub issue_type: Option<String>,
+    /// Relative weight of every issue type created, replaces `issue_type` when present
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub issue_mix: BTreeMap<String, u32>,
+    /// Keep creating issues for this long instead of stopping at `issue_count`
+    #[serde(default, with = "humantime_option")]
+    pub duration: Option<Duration>,
+    /// Target arrival rate, requests are sent on schedule regardless of response times
+    pub rps: Option<f64>,
+    /// Load profile, replaces `duration` and `rps` when present
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<Stage>,
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    /// Whole request timeout, from connecting until the body is read
+    #[serde(default = "default_timeout", with = "humantime_required")]
+    pub timeout: Duration,
+    #[serde(default = "default_connect_timeout", with = "humantime_required")]
+    pub connect_timeout: Duration,
+    /// Idle connections kept open per host, unlimited when missing
+    pub pool_max_idle_per_host: Option<usize>,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    pub report: Option<ReportFormat>,
+    pub report_file: Option<PathBuf>,
+    #[serde(default)]
+    pub report_samples: bool,
+    /// Write a self-contained HTML report to this file
+    pub html_report: Option<PathBuf>,
+    /// Write per-interval snapshots to this JSONL file
+    pub timeseries_file: Option<PathBuf>,
+    #[serde(default = "default_timeseries_interval", with = "humantime_required")]
+    pub timeseries_interval: Duration,
+    /// Serve live metrics in the Prometheus text format on this address
+    pub metrics_listen: Option<SocketAddr>,
+    #[serde(default)]
+    pub thresholds: Thresholds,
+    /// Suppress the per-issue output lines
+    #[serde(default)]
+    pub quiet: bool,
+    /// Related resources crawled for every issue
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<CrawlInclude>,
+    /// Seed of the fake data, a random one is picked and printed when missing
+    pub seed: Option<u64>,
+    /// Comments added to every created issue
+    #[serde(default)]
+    pub comments: CommentConfig,
+    #[serde(default)]
+    pub descriptions: DescriptionConfig,
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct DescriptionConfig {
+    /// Number of markdown sections after the summary, picked by weight
+    #[serde(default = "default_description_sections")]
+    pub sections: Vec<WeightedCount>,
+}
+
+impl Default for DescriptionConfig {
+    fn default() -> DescriptionConfig {
+        DescriptionConfig {
+            sections: default_description_sections(),
+        }
+    }
+}
+
+fn default_description_sections() -> Vec<WeightedCount> {
+    vec![
+        WeightedCount {
+            count: 0,
+            weight: 20,
+        },
+        WeightedCount {
+            count: 2,
+            weight: 50,
+        },
+        WeightedCount {
+            count: 5,
+            weight: 25,
+        },
+        WeightedCount {
+            count: 12,
+            weight: 5,
+        },
+    ]
+}
+
+#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+pub struct CommentConfig {
+    /// Number of comments per issue, picked by weight. No comments when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub per_issue: Vec<WeightedCount>,
+    /// Comments are posted as a random user from this pool, or with `token` when empty
+    #[serde(default, skip_serializing)]
+    pub tokens: Vec<String>,
+}
+
+/// A count picked proportionally to its weight among others
+#[derive(Clone, Copy, Deserialize, Serialize, Debug)]
+pub struct WeightedCount {
+    pub count: u32,
+    pub weight: u32,
+}
+
+/// Paginated resources of an issue that crawls can fetch after its details
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CrawlInclude {
+    Comments,
+    Timeline,
 }
 
 impl Config {
@@ -29,39 +159,277 @@ impl Config {
             }
         };
 
-        if config.issue_count == 0 || config.issue_count > 100 {
-            return Err("Issue count must be between 1 to 100");
+        if config.issue_count == 0 {
+            return Err("Issue count must be at least 1");
+        }
+
+        if config.concurrency == 0 {
+            return Err("Concurrency must be at least 1");
         }
 
+        config.validate_load()?;
+        config.retry.validate()?;
+        config.thresholds.validate()?;
+
         // Validate issue type if present
         if let Some(issue_type) = &config.issue_type {
-            let issue_types = vec![
-                String::from("initiative"),
-                String::from("epic"),
-                String::from("user_story"),
-                String::from("task"),
-                String::from("issue"),
-                String::from("feature"),
-                String::from("bug"),
-                String::from("test_case"),
-            ];
-
-            if !issue_types.contains(&issue_type) {
+            if !ISSUE_TYPES.contains(&issue_type.as_str()) {
                 return Err("Issue type is invalid.");
             }
         }
 
+        if !config.issue_mix.is_empty() {
+            if config.issue_type.is_some() {
+                return Err("Use either issue_type or issue_mix, not both.");
+            }
+            if config
+                .issue_mix
+                .keys()
+                .any(|issue_type| !ISSUE_TYPES.contains(&issue_type.as_str()))
+            {
+                return Err("Issue mix has an invalid issue type.");
+            }
+            if config.issue_mix.values().all(|weight| *weight == 0) {
+                return Err("Issue mix needs at least one weight above 0.");
+            }
+        }
+
+        if !config.comments.per_issue.is_empty()
+            && config
+                .comments
+                .per_issue
+                .iter()
+                .all(|count| count.weight == 0)
+        {
+            return Err("Comments per issue need at least one weight above 0.");
+        }
+
+        if config
+            .descriptions
+            .sections
+            .iter()
+            .all(|count| count.weight == 0)
+        {
+            return Err("Description sections need at least one weight above 0.");
+        }
+
         Ok(config)
     }
+
+    fn validate_load(&self) -> Result<(), &'static str> {
+        if let Some(rps) = self.rps {
+            if !rps.is_finite() || rps <= 0.0 {
+                return Err("Target rps must be greater than 0");
+            }
+        }
+        if self.duration.is_some_and(|duration| duration.is_zero()) {
+            return Err("Duration must be greater than 0");
+        }
+        if self.timeout.is_zero() || self.connect_timeout.is_zero() {
+            return Err("Timeouts must be greater than 0");
+        }
+        if self.timeseries_interval.is_zero() {
+            return Err("Time-series interval must be greater than 0");
+        }
+        if !self.stages.is_empty() {
+            if self.rps.is_some() || self.duration.is_some() {
+                return Err("Stages cannot be combined with duration or rps");
+            }
+            for stage in self.stages.iter() {
+                if stage.duration.is_zero() {
+                    return Err("Stage duration must be greater than 0");
+                }
+                if !stage.target_rps.is_finite() || stage.target_rps < 0.0 {
+                    return Err("Stage target rps must not be negative");
+                }
+            }
+            if self.stages.iter().all(|stage| stage.target_rps == 0.0) {
+                return Err("At least one stage must have a target rps");
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies command line overrides
+    pub fn apply_args(&mut self, args: &Args) -> Result<(), &'static str> {
+        if let Some(concurrency) = args.concurrency {
+            self.concurrency = concurrency as usize;
+        }
+        if let Some(duration) = args.duration {
+            self.duration = Some(duration);
+        }
+        if let Some(rps) = args.rps {
+            self.rps = Some(rps);
+        }
+        if let Some(timeout) = args.timeout {
+            self.timeout = timeout;
+        }
+        if let Some(report) = args.report {
+            self.report = Some(report);
+        }
+        if let Some(report_file) = &args.report_file {
+            self.report_file = Some(report_file.clone());
+        }
+        if args.report_samples {
+            self.report_samples = true;
+        }
+        if let Some(html_report) = &args.html_report {
+            self.html_report = Some(html_report.clone());
+        }
+        if let Some(timeseries_file) = &args.timeseries_file {
+            self.timeseries_file = Some(timeseries_file.clone());
+        }
+        if let Some(timeseries_interval) = args.timeseries_interval {
+            self.timeseries_interval = timeseries_interval;
+        }
+        if let Some(metrics_listen) = args.metrics_listen {
+            self.metrics_listen = Some(metrics_listen);
+        }
+        if args.quiet {
+            self.quiet = true;
+        }
+        if !args.include.is_empty() {
+            self.include = args.include.clone();
+        }
+        if let Some(seed) = args.seed {
+            self.seed = Some(seed);
+        }
+        // Always known so the report records how to reproduce the data
+        self.seed.get_or_insert_with(rand::random);
+        self.validate_load()
+    }
+}
+
+/// One step of a load profile, the rate moves linearly from the previous
+/// stage's target (or zero) to `target_rps` over `duration`
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Stage {
+    #[serde(with = "humantime_required")]
+    pub duration: Duration,
+    pub target_rps: f64,
+}
+
+fn default_concurrency() -> usize {
+    50
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_connect_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_timeseries_interval() -> Duration {
+    Duration::from_secs(5)
+}
+
+/// Reads and writes durations in human readable form, ie: "10m" or "1h 30m"
+pub mod humantime_required {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&humantime::format_duration(*value).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        humantime::parse_duration(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Same as `humantime_required` for optional durations
+pub mod humantime_option {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(duration) => {
+                serializer.serialize_str(&humantime::format_duration(*duration).to_string())
+            }
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        let value: Option<String> = Option::deserialize(deserializer)?;
+        value
+            .map(|value| humantime::parse_duration(&value).map_err(serde::de::Error::custom))
+            .transpose()
+    }
 }
 
 /// CLI tool to create issues into a project
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// TOML configuration file
+    /// TOML configuration file, required by all commands except compare
     #[arg(short, long, value_name = "FILE.toml")]
-    pub config: PathBuf,
+    pub config: Option<PathBuf>,
+
+    /// Maximum number of requests in flight, overrides the config file
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub concurrency: Option<u16>,
+
+    /// Create issues for this long, ie: 30s, 10m, 1h, overrides the config file
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub duration: Option<Duration>,
+
+    /// Target requests per second for issue creation, overrides the config file
+    #[arg(long, value_name = "RPS")]
+    pub rps: Option<f64>,
+
+    /// Request timeout, ie: 10s, overrides the config file
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Write a machine-readable run report in the given format
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub report: Option<ReportFormat>,
+
+    /// Report destination, prints to stdout when omitted
+    #[arg(long, value_name = "PATH")]
+    pub report_file: Option<PathBuf>,
+
+    /// Include every individual request sample in the report
+    #[arg(long)]
+    pub report_samples: bool,
+
+    /// Write a self-contained HTML report with charts to this file
+    #[arg(long, value_name = "PATH")]
+    pub html_report: Option<PathBuf>,
+
+    /// Write per-interval metric snapshots to a JSONL file
+    #[arg(long, value_name = "PATH")]
+    pub timeseries_file: Option<PathBuf>,
+
+    /// Time between snapshots, ie: 5s, 1m, defaults to 5s
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub timeseries_interval: Option<Duration>,
+
+    /// Serve Prometheus metrics on this address while running, ie: 127.0.0.1:9898
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_listen: Option<SocketAddr>,
+
+    /// Suppress the per-issue output lines
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Also crawl every page of these issue resources, comma separated
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "RESOURCES")]
+    pub include: Vec<CrawlInclude>,
+
+    /// Seed of the fake data, the same seed and project state yield the same payloads
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
 
     #[command(subcommand)]
     pub command: Commands,
@@ -77,4 +445,82 @@ pub enum Commands {
 
     /// Craw all issues from all visible projects
     CrawlAllIssues,
+
+    /// Load the page of every issue of the specified project like a browser does
+    CrawlIssuePages,
+
+    /// Compare two saved JSON reports and fail on regressions
+    Compare(CompareArgs),
+
+    /// Run virtual users through the journeys of a scenario file
+    Scenario(ScenarioArgs),
+
+    /// Create a tree of initiatives, epics, stories and subtasks
+    Seed(SeedArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct CompareArgs {
+    /// Baseline JSON report
+    pub baseline: PathBuf,
+
+    /// Current JSON report
+    pub current: PathBuf,
+
+    /// Allowed latency and throughput change in percent
+    #[arg(long, default_value_t = 10.0)]
+    pub tolerance: f64,
+
+    /// Allowed error rate increase in percentage points
+    #[arg(long, default_value_t = 1.0)]
+    pub error_tolerance: f64,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct ScenarioArgs {
+    /// Scenario TOML file
+    pub file: PathBuf,
+
+    /// Number of virtual users, overrides the scenario file
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub users: Option<u32>,
+
+    /// Journeys run by each virtual user, overrides the scenario file
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub iterations: Option<u32>,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct SeedArgs {
+    /// Initiatives at the top of the tree
+    #[arg(long, value_name = "N", default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..))]
+    pub initiatives: u32,
+
+    /// Epics created under every initiative
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    pub epics: u32,
+
+    /// Stories created under every epic
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    pub stories: u32,
+
+    /// Subtasks created under every story
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    pub subtasks: u32,
+}
+
+impl SeedArgs {
+    /// Issues created at every level of the tree, from initiatives down to subtasks.
+    /// None when the tree is too large to count.
+    pub fn level_counts(&self) -> Option<[u64; 4]> {
+        let initiatives = u64::from(self.initiatives);
+        let epics = initiatives.checked_mul(u64::from(self.epics))?;
+        let stories = epics.checked_mul(u64::from(self.stories))?;
+        let subtasks = stories.checked_mul(u64::from(self.subtasks))?;
+        initiatives
+            .checked_add(epics)?
+            .checked_add(stories)?
+            .checked_add(subtasks)?;
+        Some([initiatives, epics, stories, subtasks])
+    }
 }
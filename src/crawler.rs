@@ -1,713 +1,924 @@
-use anyhow::anyhow;
-use reqwest::Client;
-use std::time::Instant;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 use crate::config::Config;
-use crate::error::Result;
+use crate::error::{RequestError, Result};
 use crate::model::{
-    Actor, Authz, ChannelKey, Comment, CreateIssueBody, Issue, IssueStatus, IssueTimelineItem,
-    Label, Organisation, PaginationResult, Project, ProjectMember, Repository, User,
-    UserPreference,
+    Actor, Authz, ChannelKey, Comment, CreateCommentBody, CreateIssueBody, Issue, IssueStatus,
+    IssueTimelineItem, Label, Organisation, PaginationResult, Project, ProjectMember, Repository,
+    User, UserPreference,
 };
+use crate::progress::Progress;
+use crate::retry::RetryPolicy;
+use crate::stats::{format_ms, SharedStats};
 
 #[derive(Debug)]
 pub struct ResponseData<T> {
-    pub duration: u128,
+    pub endpoint: &'static str,
+    pub started_at: SystemTime,
+    /// Service time of the final attempt, without retries and their backoff
+    pub duration: Duration,
+    pub status: Option<u16>,
+    pub retries: u32,
     pub data: Option<T>,
+    pub error: Option<RequestError>,
 }
 
-const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36";
-const JSON_CONTENT_TYPE: &str = "application/json";
+impl<T> ResponseData<T> {
+    /// Keeps the timing and outcome but drops the body once it is no longer needed
+    pub fn without_data(self) -> ResponseData<()> {
+        ResponseData {
+            endpoint: self.endpoint,
+            started_at: self.started_at,
+            duration: self.duration,
+            status: self.status,
+            retries: self.retries,
+            data: self.data.map(|_| ()),
+            error: self.error,
+        }
+    }
 
-pub async fn fetch_iam(config: &Config) -> Result<Actor> {
-    let url = format!("{}/iam", config.base_url);
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let actor: Actor = response.json().await?;
-        Ok(actor)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch current actor. Error: {}",
-            response.status()
-        ))
+    fn new(endpoint: &'static str) -> ResponseData<T> {
+        ResponseData {
+            endpoint,
+            started_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            status: None,
+            retries: 0,
+            data: None,
+            error: None,
+        }
     }
 }
 
-pub async fn fetch_my_organisation(config: &Config) -> Result<Organisation> {
-    let url = format!("{}/iam/organisation", config.base_url);
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let org: Organisation = response.json().await?;
-        Ok(org)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch current organisation. Error: {}",
-            response.status()
-        ))
-    }
+// Logical endpoint names used to tag timed requests
+pub const PROJECTS_LIST: &str = "projects.list";
+pub const ISSUES_LIST: &str = "issues.list";
+pub const ISSUES_CREATE: &str = "issues.create";
+pub const ISSUES_GET: &str = "issues.get";
+pub const COMMENTS_LIST: &str = "comments.list";
+pub const COMMENTS_CREATE: &str = "comments.create";
+pub const TIMELINE_LIST: &str = "timeline.list";
+pub const IAM_GET: &str = "iam.get";
+pub const ORGANISATION_GET: &str = "organisation.get";
+pub const USER_GET: &str = "user.get";
+pub const PREFERENCES_GET: &str = "preferences.get";
+pub const PROJECTS_GET: &str = "projects.get";
+pub const AUTHZ_GET: &str = "authz.get";
+pub const CHANNEL_GET: &str = "channel.get";
+pub const LABELS_LIST: &str = "labels.list";
+pub const STATUSES_LIST: &str = "statuses.list";
+pub const MEMBERS_LIST: &str = "members.list";
+pub const REPOSITORIES_LIST: &str = "repositories.list";
+pub const EPICS_LIST: &str = "epics.list";
+pub const INITIATIVES_LIST: &str = "initiatives.list";
+
+/// Every endpoint name a command can record, used to catch typos in the config
+pub const ENDPOINT_NAMES: &[&str] = &[
+    PROJECTS_LIST,
+    ISSUES_LIST,
+    ISSUES_CREATE,
+    ISSUES_GET,
+    COMMENTS_LIST,
+    COMMENTS_CREATE,
+    TIMELINE_LIST,
+    IAM_GET,
+    ORGANISATION_GET,
+    USER_GET,
+    PREFERENCES_GET,
+    PROJECTS_GET,
+    AUTHZ_GET,
+    CHANNEL_GET,
+    LABELS_LIST,
+    STATUSES_LIST,
+    MEMBERS_LIST,
+    REPOSITORIES_LIST,
+    EPICS_LIST,
+    INITIATIVES_LIST,
+];
+
+/// Read-only endpoint that can be requested by name, ie: from a scenario file.
+/// Placeholders like `{project_id}` in the path are filled in by the caller.
+#[derive(Debug)]
+pub struct EndpointSpec {
+    pub name: &'static str,
+    pub path: &'static str,
+    /// Default query parameters, same as the web app sends
+    pub query: &'static [(&'static str, &'static str)],
 }
 
-pub async fn fetch_me(config: &Config) -> Result<User> {
-    let url = format!("{}/user", config.base_url);
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let user: User = response.json().await?;
-        Ok(user)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch current user. Error: {}",
-            response.status()
-        ))
-    }
+pub const ENDPOINTS: &[EndpointSpec] = &[
+    EndpointSpec {
+        name: IAM_GET,
+        path: "/iam",
+        query: &[],
+    },
+    EndpointSpec {
+        name: ORGANISATION_GET,
+        path: "/iam/organisation",
+        query: &[],
+    },
+    EndpointSpec {
+        name: USER_GET,
+        path: "/user",
+        query: &[],
+    },
+    EndpointSpec {
+        name: PREFERENCES_GET,
+        path: "/user/preferences",
+        query: &[],
+    },
+    EndpointSpec {
+        name: PROJECTS_LIST,
+        path: "/projects",
+        query: &[
+            ("status", "active"),
+            ("page", "1"),
+            ("per_page", "50"),
+            ("sort", "-lastActivityDate"),
+        ],
+    },
+    EndpointSpec {
+        name: PROJECTS_GET,
+        path: "/projects/{project_id}",
+        query: &[("include", "organisation")],
+    },
+    EndpointSpec {
+        name: AUTHZ_GET,
+        path: "/user/authContext/projects/{project_id}",
+        query: &[],
+    },
+    EndpointSpec {
+        name: CHANNEL_GET,
+        path: "/projects/{project_id}/channels/key",
+        query: &[],
+    },
+    EndpointSpec {
+        name: LABELS_LIST,
+        path: "/projects/{project_id}/labels",
+        query: &[],
+    },
+    EndpointSpec {
+        name: STATUSES_LIST,
+        path: "/projects/{project_id}/issueStatuses",
+        query: &[],
+    },
+    EndpointSpec {
+        name: MEMBERS_LIST,
+        path: "/iam/projects/{project_id}/members/",
+        query: &[("status", "active")],
+    },
+    EndpointSpec {
+        name: REPOSITORIES_LIST,
+        path: "/projects/{project_id}/repositories",
+        query: &[],
+    },
+    EndpointSpec {
+        name: EPICS_LIST,
+        path: "/projects/{project_id}/issues",
+        query: &[
+            ("type", "epic"),
+            ("state", "active"),
+            ("page", "1"),
+            ("per_page", "50"),
+            ("sort", "-createdAt"),
+            (
+                "include",
+                "createdBy,assignee,developmentUpdates,isFollower,subtasksCount",
+            ),
+        ],
+    },
+    EndpointSpec {
+        name: ISSUES_LIST,
+        path: "/projects/{project_id}/issues",
+        query: &[
+            ("state", "active"),
+            ("page", "1"),
+            ("per_page", "50"),
+            ("sort", "-createdAt"),
+            (
+                "include",
+                "createdBy,assignee,developmentUpdates,isFollower,subtasksCount,meta",
+            ),
+        ],
+    },
+    EndpointSpec {
+        name: ISSUES_GET,
+        path: "/projects/{project_id}/issues/{issue_id}",
+        query: &[(
+            "include",
+            "isCreator,isAssignee,isFollower,initiative,epic,parent,commitment,subtasksCount",
+        )],
+    },
+    EndpointSpec {
+        name: COMMENTS_LIST,
+        path: "/projects/{project_id}/issues/{issue_id}/comments",
+        query: &[("page", "1"), ("per_page", "50"), ("sort", "-createdAt")],
+    },
+    EndpointSpec {
+        name: TIMELINE_LIST,
+        path: "/projects/{project_id}/issues/{issue_id}/timelineitems",
+        query: &[
+            ("page", "1"),
+            ("per_page", "50"),
+            ("sort", "-createdAt"),
+            ("include", "meta,commens"),
+        ],
+    },
+];
+
+pub fn find_endpoint(name: &str) -> Option<&'static EndpointSpec> {
+    ENDPOINTS.iter().find(|endpoint| endpoint.name == name)
 }
 
-pub async fn fetch_user_preferences(config: &Config) -> Result<Vec<UserPreference>> {
-    let url = format!("{}/user/preferences", config.base_url);
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let prefs: Vec<UserPreference> = response.json().await?;
-        Ok(prefs)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch current user preferences. Error: {}",
-            response.status()
-        ))
+/// Requests the web app sends before it knows which project is opened
+const ISSUE_PAGE_SHELL: &[&str] = &[IAM_GET, ORGANISATION_GET, PREFERENCES_GET];
+
+/// Requests for the project and the issue, sent together once the route is resolved
+const ISSUE_PAGE_RESOURCES: &[&str] = &[
+    PROJECTS_GET,
+    AUTHZ_GET,
+    CHANNEL_GET,
+    EPICS_LIST,
+    REPOSITORIES_LIST,
+    LABELS_LIST,
+    STATUSES_LIST,
+    MEMBERS_LIST,
+    ISSUES_GET,
+    TIMELINE_LIST,
+    COMMENTS_LIST,
+];
+
+/// Requests sent for every issue page
+pub const ISSUE_PAGE_REQUESTS: usize = ISSUE_PAGE_SHELL.len() + ISSUE_PAGE_RESOURCES.len();
+
+/// Connections a browser opens to the same host over HTTP/1.1
+const BROWSER_CONNECTIONS: usize = 6;
+
+/// Every request made to display an issue page
+#[derive(Debug)]
+pub struct IssuePage {
+    /// From the first request sent to the last response received
+    pub duration: Duration,
+    pub responses: Vec<ResponseData<serde_json::Value>>,
+}
+
+impl IssuePage {
+    /// The page only renders when all of its requests succeeded
+    pub fn is_complete(&self) -> bool {
+        self.responses.iter().all(|res| res.data.is_some())
     }
 }
-pub async fn fetch_projects(
-    config: &Config,
-    page: u32,
-    per_page: u32,
-) -> Result<PaginationResult<Project>> {
-    let url = format!("{}/projects", config.base_url.as_str());
-    let query_params = vec![
-        ("status", "active".to_string()),
-        ("page", page.to_string()),
-        ("per_page", per_page.to_string()),
-        ("sort", "-lastActivityDate".to_string()),
-        (
-            "include",
-            "meta,activeSprint,members,organisation".to_string(),
-        ),
-    ];
-
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let result: PaginationResult<Project> = response.json().await?;
-        Ok(result)
-    } else {
-        let message = format!(
-            "Unable to fetch project listing. Error: {}",
-            response.status()
+
+const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36";
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// API client sharing a single connection pool across all requests.
+/// Cloning is cheap and clones share the same pool.
+#[derive(Clone, Debug)]
+pub struct ApiClient {
+    client: Client,
+    base_url: String,
+    token: String,
+    retry: RetryPolicy,
+    progress: Progress,
+    /// Only collected when a time-series file is configured
+    interval_stats: Option<SharedStats>,
+    /// Only collected when the metrics endpoint is enabled
+    live_stats: Option<SharedStats>,
+    /// Skip the per-issue output lines
+    quiet: bool,
+}
+
+impl ApiClient {
+    pub fn new(config: &Config) -> Result<ApiClient> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            HeaderValue::from_static(JSON_CONTENT_TYPE),
         );
-        eprintln!("{}", message);
-        Err(anyhow!(message))
+
+        let mut builder = Client::builder()
+            .user_agent(USER_AGENT)
+            .default_headers(headers)
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout);
+        if let Some(max_idle) = config.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        let client = builder.build()?;
+
+        Ok(ApiClient {
+            client,
+            base_url: config.base_url.clone(),
+            token: config.token.clone(),
+            retry: config.retry.clone(),
+            progress: Progress::default(),
+            interval_stats: config
+                .timeseries_file
+                .as_ref()
+                .map(|_| SharedStats::default()),
+            live_stats: config.metrics_listen.map(|_| SharedStats::default()),
+            quiet: config.quiet,
+        })
     }
-}
 
-pub async fn fetch_project(config: &Config, project_id: &str) -> Result<Project> {
-    let url = format!("{}/projects/{}", config.base_url.as_str(), project_id);
-    let query_params = vec![("include", "organisation".to_string())];
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let project: Project = response.json().await?;
-        Ok(project)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch project {}. Error: {}",
-            config.project_id.as_str(),
-            response.status()
-        ))
+    pub fn progress(&self) -> &Progress {
+        &self.progress
     }
-}
 
-pub async fn fetch_project_authz(config: &Config, project_id: &str) -> Result<Authz> {
-    let url = format!(
-        "{}/user/authContext/projects/{}",
-        config.base_url.as_str(),
-        project_id
-    );
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let authz: Authz = response.json().await?;
-        Ok(authz)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch project permissions {}. Error: {}",
-            config.project_id.as_str(),
-            response.status()
-        ))
+    pub fn interval_stats(&self) -> Option<&SharedStats> {
+        self.interval_stats.as_ref()
     }
-}
 
-pub async fn fetch_labels(config: &Config, project_id: &str) -> Result<Vec<Label>> {
-    let url = format!(
-        "{}/projects/{}/labels",
-        config.base_url.as_str(),
-        project_id
-    );
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let labels: Vec<Label> = response.json().await?;
-        Ok(labels)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch project labels {}. Error: {}",
-            project_id,
-            response.status()
-        ))
+    pub fn live_stats(&self) -> Option<&SharedStats> {
+        self.live_stats.as_ref()
     }
-}
 
-pub async fn fetch_statuses(config: &Config, project_id: &str) -> Result<Vec<IssueStatus>> {
-    let url = format!(
-        "{}/projects/{}/issueStatuses",
-        config.base_url.as_str(),
-        project_id
-    );
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let statuses: Vec<IssueStatus> = response.json().await?;
-        Ok(statuses)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch project issue statuses {}. Error: {}",
-            project_id,
-            response.status()
-        ))
+    fn get(&self, url: String) -> RequestBuilder {
+        self.client.get(url).bearer_auth(self.token.as_str())
     }
-}
 
-pub async fn fetch_initiatives(config: &Config, project_id: &str) -> Result<Vec<Issue>> {
-    let url = format!(
-        "{}/projects/{}/issues",
-        config.base_url.as_str(),
-        project_id
-    );
-    let query_params = vec![
-        ("type", "initiative".to_string()),
-        ("state", "active".to_string()),
-        ("page", "1".to_string()),
-        ("per_page", "50".to_string()),
-        ("sort", "-createdAt".to_string()),
-        (
-            "include",
-            "createdBy,assignee,developmentUpdates,isFollower,subtasksCount".to_string(),
-        ),
-    ];
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let issues: Vec<Issue> = response.json().await?;
-        Ok(issues)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch epics. Error: {}",
-            response.status()
-        ))
+    fn post(&self, url: String) -> RequestBuilder {
+        self.client.post(url).bearer_auth(self.token.as_str())
     }
-}
-pub async fn fetch_epics(config: &Config, project_id: &str) -> Result<Vec<Issue>> {
-    let url = format!(
-        "{}/projects/{}/issues",
-        config.base_url.as_str(),
-        project_id
-    );
-    let query_params = vec![
-        ("type", "epic".to_string()),
-        ("state", "active".to_string()),
-        ("page", "1".to_string()),
-        ("per_page", "50".to_string()),
-        ("sort", "-createdAt".to_string()),
-        (
-            "include",
-            "createdBy,assignee,developmentUpdates,isFollower,subtasksCount".to_string(),
-        ),
-    ];
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let issues: Vec<Issue> = response.json().await?;
-        Ok(issues)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch epics. Error: {}",
-            response.status()
-        ))
+
+    /// Sends the request, retrying transient failures according to the retry policy.
+    /// `sent` is updated when each attempt starts so backoff delays can be left out of timings.
+    async fn send_with_retries(
+        &self,
+        request: RequestBuilder,
+        retries: &mut u32,
+        sent: &mut Instant,
+    ) -> reqwest::Result<Response> {
+        let mut attempt: u32 = 1;
+
+        loop {
+            let Some(current) = request.try_clone() else {
+                return request.send().await;
+            };
+
+            let has_more = attempt < self.retry.max_attempts;
+            *sent = Instant::now();
+            let delay = match current.send().await {
+                Ok(response) => {
+                    if !has_more || !self.retry.is_retryable_status(response.status()) {
+                        return Ok(response);
+                    }
+                    self.retry.delay(attempt, Some(response.headers()))
+                }
+                Err(err) => {
+                    if !has_more || !self.retry.is_retryable_error(&err) {
+                        return Err(err);
+                    }
+                    self.retry.delay(attempt, None)
+                }
+            };
+
+            *retries += 1;
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
-}
 
-pub async fn fetch_members(config: &Config, project_id: &str) -> Result<Vec<ProjectMember>> {
-    let url = format!(
-        "{}/iam/projects/{}/members/?status=active",
-        config.base_url.as_str(),
-        project_id
-    );
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let members: Vec<ProjectMember> = response.json().await?;
-        Ok(members)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch project members. Error: {}",
-            response.status()
-        ))
+    /// Sends the request and decodes the response, capturing timing and status
+    async fn send_timed<T: DeserializeOwned>(
+        &self,
+        endpoint: &'static str,
+        request: RequestBuilder,
+        error_message: &str,
+    ) -> ResponseData<T> {
+        let mut res: ResponseData<T> = ResponseData::new(endpoint);
+
+        let mut sent = Instant::now();
+        match self
+            .send_with_retries(request, &mut res.retries, &mut sent)
+            .await
+        {
+            Ok(response) => {
+                let status = response.status();
+                res.status = Some(status.as_u16());
+                match response.bytes().await {
+                    Ok(body) if status.is_success() => match serde_json::from_slice::<T>(&body) {
+                        Ok(data) => res.data = Some(data),
+                        Err(err) => {
+                            res.error = Some(RequestError::decode(status, &err, &body));
+                        }
+                    },
+                    Ok(body) => res.error = Some(RequestError::from_status(status, &body)),
+                    Err(err) => res.error = Some(RequestError::from_reqwest(&err)),
+                }
+            }
+            Err(err) => res.error = Some(RequestError::from_reqwest(&err)),
+        }
+        if let Some(err) = &res.error {
+            self.print_error(format!("{}. Error: {}", error_message, err).as_str());
+        }
+        // Only the final attempt is timed, earlier ones are counted as retries
+        res.duration = sent.elapsed();
+        res.started_at = SystemTime::now()
+            .checked_sub(res.duration)
+            .unwrap_or(res.started_at);
+        self.progress.record(&res);
+        if let Some(interval_stats) = &self.interval_stats {
+            interval_stats.record(&res);
+        }
+        if let Some(live_stats) = &self.live_stats {
+            live_stats.record(&res);
+        }
+
+        res
     }
-}
 
-pub async fn fetch_project_repositories(
-    config: &Config,
-    project_id: &str,
-) -> Result<Vec<Repository>> {
-    let url = format!(
-        "{}/projects/{}/repositories",
-        config.base_url.as_str(),
-        project_id
-    );
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let list: Vec<Repository> = response.json().await?;
-        Ok(list)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch project repositories. Error: {}",
-            response.status()
-        ))
+    /// Failures are summarized in the error table, so `--quiet` hides them as well
+    pub fn print_error(&self, message: &str) {
+        if !self.quiet {
+            self.progress.eprintln(message);
+        }
     }
-}
 
-pub async fn fetch_project_channel(config: &Config, project_id: &str) -> Result<ChannelKey> {
-    let url = format!(
-        "{}/projects/{}/channels/key",
-        config.base_url.as_str(),
-        project_id
-    );
-    let response = Client::new()
-        .get(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let key: ChannelKey = response.json().await?;
-        Ok(key)
-    } else {
-        Err(anyhow!(
-            "Unable to fetch project channel key. Error: {}",
-            response.status()
-        ))
+    fn print_issue(&self, issue: &Issue, duration: Duration) {
+        if !self.quiet {
+            self.progress.println(
+                format!("{}: {} --> {}", issue.key, issue.title, format_ms(duration)).as_str(),
+            );
+        }
     }
-}
 
-pub async fn create_issue(
-    config: &Config,
-    project_id: &str,
-    payload: &CreateIssueBody,
-) -> Result<ResponseData<Issue>> {
-    let mut res: ResponseData<Issue> = ResponseData {
-        duration: 0,
-        data: None,
-    };
-
-    let d = Instant::now();
-    let create_res = do_create_issue(config, project_id, payload).await;
-    res.duration = d.elapsed().as_millis();
-    if let Ok(issue_res) = create_res {
-        println!(
-            "{}: {} --> {} ms",
-            issue_res.key, issue_res.title, res.duration
+    /// Requests a catalog endpoint by its resolved path, the body is kept as raw JSON
+    pub async fn fetch_endpoint(
+        &self,
+        endpoint: &EndpointSpec,
+        path: &str,
+        query: &[(String, String)],
+    ) -> ResponseData<serde_json::Value> {
+        let url = format!("{}{}", self.base_url.as_str(), path);
+        let error_message = format!("Unable to fetch {}", endpoint.name);
+        self.send_timed(
+            endpoint.name,
+            self.get(url).query(query),
+            error_message.as_str(),
+        )
+        .await
+    }
+
+    pub async fn fetch_iam(&self) -> Result<ResponseData<Actor>> {
+        let url = format!("{}/iam", self.base_url);
+        let res = self
+            .send_timed(IAM_GET, self.get(url), "Unable to fetch current actor")
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_my_organisation(&self) -> Result<ResponseData<Organisation>> {
+        let url = format!("{}/iam/organisation", self.base_url);
+        let res = self
+            .send_timed(
+                ORGANISATION_GET,
+                self.get(url),
+                "Unable to fetch current organisation",
+            )
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_me(&self) -> Result<ResponseData<User>> {
+        let url = format!("{}/user", self.base_url);
+        let res = self
+            .send_timed(USER_GET, self.get(url), "Unable to fetch current user")
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_user_preferences(&self) -> Result<ResponseData<Vec<UserPreference>>> {
+        let url = format!("{}/user/preferences", self.base_url);
+        let res = self
+            .send_timed(
+                PREFERENCES_GET,
+                self.get(url),
+                "Unable to fetch current user preferences",
+            )
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_projects(
+        &self,
+        page: u32,
+        per_page: u32,
+    ) -> Result<ResponseData<PaginationResult<Project>>> {
+        let url = format!("{}/projects", self.base_url.as_str());
+        let query_params = vec![
+            ("status", "active".to_string()),
+            ("page", page.to_string()),
+            ("per_page", per_page.to_string()),
+            ("sort", "-lastActivityDate".to_string()),
+            (
+                "include",
+                "meta,activeSprint,members,organisation".to_string(),
+            ),
+        ];
+
+        let res = self
+            .send_timed(
+                PROJECTS_LIST,
+                self.get(url).query(&query_params),
+                "Unable to fetch project listing",
+            )
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_project(&self, project_id: &str) -> Result<ResponseData<Project>> {
+        let url = format!("{}/projects/{}", self.base_url.as_str(), project_id);
+        let query_params = vec![("include", "organisation".to_string())];
+        let error_message = format!("Unable to fetch project {}", project_id);
+        let res = self
+            .send_timed(
+                PROJECTS_GET,
+                self.get(url).query(&query_params),
+                error_message.as_str(),
+            )
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_project_authz(&self, project_id: &str) -> Result<ResponseData<Authz>> {
+        let url = format!(
+            "{}/user/authContext/projects/{}",
+            self.base_url.as_str(),
+            project_id
         );
-        res.data = Some(issue_res);
+        let error_message = format!("Unable to fetch project permissions {}", project_id);
+        let res = self
+            .send_timed(AUTHZ_GET, self.get(url), error_message.as_str())
+            .await;
+
+        Ok(res)
     }
 
-    Ok(res)
-}
+    pub async fn fetch_labels(&self, project_id: &str) -> Result<ResponseData<Vec<Label>>> {
+        let url = format!("{}/projects/{}/labels", self.base_url.as_str(), project_id);
+        let error_message = format!("Unable to fetch project labels {}", project_id);
+        let res = self
+            .send_timed(LABELS_LIST, self.get(url), error_message.as_str())
+            .await;
 
-async fn do_create_issue(
-    config: &Config,
-    project_id: &str,
-    payload: &CreateIssueBody,
-) -> Result<Issue> {
-    let url = format!(
-        "{}/projects/{}/issues",
-        config.base_url.as_str(),
-        project_id
-    );
-    let post_body = serde_json::to_string(payload)?;
-
-    let response = Client::new()
-        .post(url)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .body(post_body)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let issue: Issue = response.json().await?;
-        Ok(issue)
-    } else {
-        let message = format!("Unable to create issue. Error: {}", response.status());
-        eprintln!("{}", message);
-        Err(anyhow!(message))
+        Ok(res)
     }
-}
 
-pub async fn fetch_issues(
-    config: &Config,
-    project_id: &str,
-    page: u32,
-    per_page: u32,
-) -> Result<PaginationResult<Issue>> {
-    let url = format!(
-        "{}/projects/{}/issues",
-        config.base_url.as_str(),
-        project_id
-    );
-    let query_params = vec![
-        ("state", "active".to_string()),
-        ("page", page.to_string()),
-        ("per_page", per_page.to_string()),
-        ("sort", "-createdAt".to_string()),
-        (
-            "include",
-            "createdBy,assignee,developmentUpdates,isFollower,subtasksCount,meta".to_string(),
-        ),
-    ];
-
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let result: PaginationResult<Issue> = response.json().await?;
-        Ok(result)
-    } else {
-        let message = format!(
-            "Unable to fetch issue listing. Error: {}",
-            response.status()
+    pub async fn fetch_statuses(&self, project_id: &str) -> Result<ResponseData<Vec<IssueStatus>>> {
+        let url = format!(
+            "{}/projects/{}/issueStatuses",
+            self.base_url.as_str(),
+            project_id
         );
-        eprintln!("{}", message);
-        Err(anyhow!(message))
+        let error_message = format!("Unable to fetch project issue statuses {}", project_id);
+        let res = self
+            .send_timed(STATUSES_LIST, self.get(url), error_message.as_str())
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_initiatives(&self, project_id: &str) -> Result<ResponseData<Vec<Issue>>> {
+        let url = format!("{}/projects/{}/issues", self.base_url.as_str(), project_id);
+        let query_params = vec![
+            ("type", "initiative".to_string()),
+            ("state", "active".to_string()),
+            ("page", "1".to_string()),
+            ("per_page", "50".to_string()),
+            ("sort", "-createdAt".to_string()),
+            (
+                "include",
+                "createdBy,assignee,developmentUpdates,isFollower,subtasksCount".to_string(),
+            ),
+        ];
+        let res = self
+            .send_timed(
+                INITIATIVES_LIST,
+                self.get(url).query(&query_params),
+                "Unable to fetch initiatives",
+            )
+            .await;
+
+        Ok(res)
     }
-}
 
-pub async fn fetch_issue(
-    config: &Config,
-    project_id: &str,
-    issue_id: &str,
-) -> Result<ResponseData<Issue>> {
-    let mut res: ResponseData<Issue> = ResponseData {
-        duration: 0,
-        data: None,
-    };
-
-    let d = Instant::now();
-    let create_res = do_fetch_issue(config, project_id, issue_id).await;
-    res.duration = d.elapsed().as_millis();
-    if let Ok(issue_res) = create_res {
-        println!(
-            "{}: {} --> {} ms",
-            issue_res.key, issue_res.title, res.duration
+    pub async fn fetch_epics(&self, project_id: &str) -> Result<ResponseData<Vec<Issue>>> {
+        let url = format!("{}/projects/{}/issues", self.base_url.as_str(), project_id);
+        let query_params = vec![
+            ("type", "epic".to_string()),
+            ("state", "active".to_string()),
+            ("page", "1".to_string()),
+            ("per_page", "50".to_string()),
+            ("sort", "-createdAt".to_string()),
+            (
+                "include",
+                "createdBy,assignee,developmentUpdates,isFollower,subtasksCount".to_string(),
+            ),
+        ];
+        let res = self
+            .send_timed(
+                EPICS_LIST,
+                self.get(url).query(&query_params),
+                "Unable to fetch epics",
+            )
+            .await;
+
+        Ok(res)
+    }
+
+    pub async fn fetch_members(
+        &self,
+        project_id: &str,
+    ) -> Result<ResponseData<Vec<ProjectMember>>> {
+        let url = format!(
+            "{}/iam/projects/{}/members/?status=active",
+            self.base_url.as_str(),
+            project_id
         );
-        res.data = Some(issue_res);
+        let res = self
+            .send_timed(
+                MEMBERS_LIST,
+                self.get(url),
+                "Unable to fetch project members",
+            )
+            .await;
+
+        Ok(res)
     }
 
-    Ok(res)
-}
+    pub async fn fetch_project_repositories(
+        &self,
+        project_id: &str,
+    ) -> Result<ResponseData<Vec<Repository>>> {
+        let url = format!(
+            "{}/projects/{}/repositories",
+            self.base_url.as_str(),
+            project_id
+        );
+        let res = self
+            .send_timed(
+                REPOSITORIES_LIST,
+                self.get(url),
+                "Unable to fetch project repositories",
+            )
+            .await;
+
+        Ok(res)
+    }
 
-async fn do_fetch_issue(config: &Config, project_id: &str, issue_id: &str) -> Result<Issue> {
-    let url = format!(
-        "{}/projects/{}/issues/{}",
-        config.base_url.as_str(),
-        project_id,
-        issue_id
-    );
-    let query_params = vec![(
-        "include",
-        "isCreator,isAssignee,isFollower,initiative,epic,parent,commitment,subtasksCount"
-            .to_string(),
-    )];
-
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let issue: Issue = response.json().await?;
-        Ok(issue)
-    } else {
-        let message = format!("Unable to fetch issue. Error: {}", response.status());
-        eprintln!("{}", message);
-        Err(anyhow!(message))
+    pub async fn fetch_project_channel(
+        &self,
+        project_id: &str,
+    ) -> Result<ResponseData<ChannelKey>> {
+        let url = format!(
+            "{}/projects/{}/channels/key",
+            self.base_url.as_str(),
+            project_id
+        );
+        let res = self
+            .send_timed(
+                CHANNEL_GET,
+                self.get(url),
+                "Unable to fetch project channel key",
+            )
+            .await;
+
+        Ok(res)
     }
-}
 
-pub async fn fetch_issue_comments(
-    config: &Config,
-    project_id: &str,
-    issue_id: &str,
-    page: u32,
-    per_page: u32,
-) -> Result<ResponseData<PaginationResult<Comment>>> {
-    let mut res: ResponseData<PaginationResult<Comment>> = ResponseData {
-        duration: 0,
-        data: None,
-    };
-
-    let d = Instant::now();
-    let listing_res = do_fetch_issue_comments(config, project_id, issue_id, page, per_page).await;
-    res.duration = d.elapsed().as_millis();
-    if let Ok(listing) = listing_res {
-        res.data = Some(listing);
-    }
-
-    Ok(res)
-}
+    pub async fn create_issue(
+        &self,
+        project_id: &str,
+        payload: &CreateIssueBody,
+    ) -> Result<ResponseData<Issue>> {
+        let url = format!("{}/projects/{}/issues", self.base_url.as_str(), project_id);
+        let post_body = serde_json::to_string(payload)?;
+
+        let res: ResponseData<Issue> = self
+            .send_timed(
+                ISSUES_CREATE,
+                self.post(url).body(post_body),
+                "Unable to create issue",
+            )
+            .await;
+
+        if let Some(issue) = &res.data {
+            self.print_issue(issue, res.duration);
+        }
+
+        Ok(res)
+    }
+
+    pub async fn fetch_issues(
+        &self,
+        project_id: &str,
+        page: u32,
+        per_page: u32,
+    ) -> Result<ResponseData<PaginationResult<Issue>>> {
+        let url = format!("{}/projects/{}/issues", self.base_url.as_str(), project_id);
+        let query_params = vec![
+            ("state", "active".to_string()),
+            ("page", page.to_string()),
+            ("per_page", per_page.to_string()),
+            ("sort", "-createdAt".to_string()),
+            (
+                "include",
+                "createdBy,assignee,developmentUpdates,isFollower,subtasksCount,meta".to_string(),
+            ),
+        ];
+
+        let res = self
+            .send_timed(
+                ISSUES_LIST,
+                self.get(url).query(&query_params),
+                "Unable to fetch issue listing",
+            )
+            .await;
+
+        Ok(res)
+    }
 
-pub async fn do_fetch_issue_comments(
-    config: &Config,
-    project_id: &str,
-    issue_id: &str,
-    page: u32,
-    per_page: u32,
-) -> Result<PaginationResult<Comment>> {
-    let url = format!(
-        "{}/projects/{}/issues/{}/comments",
-        config.base_url.as_str(),
-        project_id,
-        issue_id
-    );
-    let query_params = vec![
-        ("page", page.to_string()),
-        ("per_page", per_page.to_string()),
-        ("sort", "-createdAt".to_string()),
-    ];
-
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let result: PaginationResult<Comment> = response.json().await?;
-        Ok(result)
-    } else {
-        let message = format!(
-            "Unable to fetch issue comment listing. Error: {}",
-            response.status()
+    pub async fn fetch_issue(
+        &self,
+        project_id: &str,
+        issue_id: &str,
+    ) -> Result<ResponseData<Issue>> {
+        let url = format!(
+            "{}/projects/{}/issues/{}",
+            self.base_url.as_str(),
+            project_id,
+            issue_id
         );
-        eprintln!("{}", message);
-        Err(anyhow!(message))
+        let query_params = vec![(
+            "include",
+            "isCreator,isAssignee,isFollower,initiative,epic,parent,commitment,subtasksCount"
+                .to_string(),
+        )];
+
+        let res: ResponseData<Issue> = self
+            .send_timed(
+                ISSUES_GET,
+                self.get(url).query(&query_params),
+                "Unable to fetch issue",
+            )
+            .await;
+
+        if let Some(issue) = &res.data {
+            self.print_issue(issue, res.duration);
+        }
+
+        Ok(res)
     }
-}
 
-pub async fn fetch_issue_timeline_items(
-    config: &Config,
-    project_id: &str,
-    issue_id: &str,
-    page: u32,
-    per_page: u32,
-) -> Result<ResponseData<PaginationResult<IssueTimelineItem>>> {
-    let mut res: ResponseData<PaginationResult<IssueTimelineItem>> = ResponseData {
-        duration: 0,
-        data: None,
-    };
-
-    let d = Instant::now();
-    let listing_res =
-        do_fetch_issue_timeline_items(config, project_id, issue_id, page, per_page).await;
-    res.duration = d.elapsed().as_millis();
-    if let Ok(listing) = listing_res {
-        res.data = Some(listing);
-    }
-
-    Ok(res)
-}
+    pub async fn fetch_issue_comments(
+        &self,
+        project_id: &str,
+        issue_id: &str,
+        page: u32,
+        per_page: u32,
+    ) -> Result<ResponseData<PaginationResult<Comment>>> {
+        let url = format!(
+            "{}/projects/{}/issues/{}/comments",
+            self.base_url.as_str(),
+            project_id,
+            issue_id
+        );
+        let query_params = vec![
+            ("page", page.to_string()),
+            ("per_page", per_page.to_string()),
+            ("sort", "-createdAt".to_string()),
+        ];
+
+        let res = self
+            .send_timed(
+                COMMENTS_LIST,
+                self.get(url).query(&query_params),
+                "Unable to fetch issue comment listing",
+            )
+            .await;
+
+        Ok(res)
+    }
 
-pub async fn do_fetch_issue_timeline_items(
-    config: &Config,
-    project_id: &str,
-    issue_id: &str,
-    page: u32,
-    per_page: u32,
-) -> Result<PaginationResult<IssueTimelineItem>> {
-    let url = format!(
-        "{}/projects/{}/issues/{}/timelineitems",
-        config.base_url.as_str(),
-        project_id,
-        issue_id
-    );
-    let query_params = vec![
-        ("page", page.to_string()),
-        ("per_page", per_page.to_string()),
-        ("sort", "-createdAt".to_string()),
-        ("include", "meta,commens".to_string()),
-    ];
-
-    let response = Client::new()
-        .get(url)
-        .query(&query_params)
-        .header(reqwest::header::USER_AGENT, USER_AGENT)
-        .header(reqwest::header::CONTENT_TYPE, JSON_CONTENT_TYPE)
-        .bearer_auth(config.token.as_str())
-        .send()
-        .await?;
-
-    if response.status().is_success() {
-        let result: PaginationResult<IssueTimelineItem> = response.json().await?;
-        Ok(result)
-    } else {
-        let message = format!(
-            "Unable to fetch issue timelime itemlisting. Error: {}",
-            response.status()
+    /// Posts a comment, as the user of `token` when given
+    pub async fn create_comment(
+        &self,
+        project_id: &str,
+        issue_id: &str,
+        payload: &CreateCommentBody,
+        token: Option<&str>,
+    ) -> Result<ResponseData<Comment>> {
+        let url = format!(
+            "{}/projects/{}/issues/{}/comments",
+            self.base_url.as_str(),
+            project_id,
+            issue_id
         );
-        eprintln!("{}", message);
-        Err(anyhow!(message))
+        let post_body = serde_json::to_string(payload)?;
+        let token = token.unwrap_or(self.token.as_str());
+
+        let res = self
+            .send_timed(
+                COMMENTS_CREATE,
+                self.client.post(url).bearer_auth(token).body(post_body),
+                "Unable to create comment",
+            )
+            .await;
+
+        Ok(res)
     }
-}
 
-pub async fn fetch_issue_page_resources(
-    config: &Config,
-    project_id: &str,
-    issue_id: &str,
-) -> Result<PaginationResult<Comment>> {
-    // Fetch all resources in an issue page
-    // Fetch iam
-    // Fetch organisation
-    // Fetch user preferences
-    // Fetch all organisations
-    // Fetch recent projects
-    // Fetch project
-    // Fetch project authz
-    // Fetch project channel
-    // Fetch project epics
-    // Fetch project repositories (if has access)
-    // Fetch project labels
-    // Fetch project statuses
-    // Fetch project members (if has access)
-    // Fetch project Github integration (if has access)
-    // Fetch my project file quota
-    // Fetch account notifications
-    // Fetch regular notifications
-    // Fetch sprints (if scrum project)
-    // Fetch issue
-    // Fetch issue files
-    // Fetch issue subtasks if issue is not a subtask
-    // Fetch issue timeline items
-    // Fetch issue followers
-    // Fetch development updates (if has Github integration)
-    // Fetch issue repo branches
-    // Fetch issue comments count
-    Err(anyhow!("Error"))
+    pub async fn fetch_issue_timeline_items(
+        &self,
+        project_id: &str,
+        issue_id: &str,
+        page: u32,
+        per_page: u32,
+    ) -> Result<ResponseData<PaginationResult<IssueTimelineItem>>> {
+        let url = format!(
+            "{}/projects/{}/issues/{}/timelineitems",
+            self.base_url.as_str(),
+            project_id,
+            issue_id
+        );
+        let query_params = vec![
+            ("page", page.to_string()),
+            ("per_page", per_page.to_string()),
+            ("sort", "-createdAt".to_string()),
+            ("include", "meta,commens".to_string()),
+        ];
+
+        let res = self
+            .send_timed(
+                TIMELINE_LIST,
+                self.get(url).query(&query_params),
+                "Unable to fetch issue timelime item listing",
+            )
+            .await;
+
+        Ok(res)
+    }
+
+    /// Loads an issue page the way a browser does, the app shell first then the project
+    /// and issue resources, with at most `BROWSER_CONNECTIONS` requests in flight
+    pub async fn fetch_issue_page_resources(&self, project_id: &str, issue_id: &str) -> IssuePage {
+        let connections = Arc::new(Semaphore::new(BROWSER_CONNECTIONS));
+        let mut responses: Vec<ResponseData<serde_json::Value>> = Vec::new();
+
+        let d = Instant::now();
+        for wave in [ISSUE_PAGE_SHELL, ISSUE_PAGE_RESOURCES] {
+            let mut tasks = JoinSet::new();
+            for name in wave {
+                let endpoint = find_endpoint(name).expect("Issue page endpoint not in catalog");
+                let path = endpoint
+                    .path
+                    .replace("{project_id}", project_id)
+                    .replace("{issue_id}", issue_id);
+                let query: Vec<(String, String)> = endpoint
+                    .query
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                let client = self.clone();
+                let connections = connections.clone();
+                tasks.spawn(async move {
+                    let _permit = connections.acquire_owned().await.unwrap();
+                    client.fetch_endpoint(endpoint, path.as_str(), &query).await
+                });
+            }
+            while let Some(res) = tasks.join_next().await {
+                responses.push(res.unwrap());
+            }
+        }
+        let page = IssuePage {
+            duration: d.elapsed(),
+            responses,
+        };
+
+        let issue = page
+            .responses
+            .iter()
+            .find(|res| res.endpoint == ISSUES_GET)
+            .and_then(|res| res.data.clone())
+            .and_then(|data| serde_json::from_value::<Issue>(data).ok());
+        if let Some(issue) = issue {
+            self.print_issue(&issue, page.duration);
+        }
+
+        page
+    }
 }
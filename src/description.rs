@@ -0,0 +1,181 @@
+use fake::faker::company::en::{Bs, BsNoun, Buzzword, CatchPhase};
+use fake::faker::internet::en::DomainSuffix;
+use fake::faker::lorem::en::{Paragraphs, Sentence, Word, Words};
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::fmt::Write as _;
+
+/// Sections that can follow the summary of any issue type
+#[derive(Clone, Copy)]
+enum Section {
+    Details,
+    Notes,
+    AcceptanceCriteria,
+    Code,
+    Links,
+}
+
+const SECTIONS: [Section; 5] = [
+    Section::Details,
+    Section::Notes,
+    Section::AcceptanceCriteria,
+    Section::Code,
+    Section::Links,
+];
+
+const CODE_LANGUAGES: [&str; 4] = ["json", "sh", "rust", "sql"];
+
+/// Markdown description made of a summary followed by `sections` sections.
+/// Bugs start with steps to reproduce, stories with the user story itself.
+pub fn generate(rng: &mut StdRng, issue_type: &str, sections: u32) -> String {
+    let mut out = String::new();
+    let paragraphs: Vec<String> = Paragraphs(1..3).fake_with_rng(rng);
+    out.push_str(&paragraphs.join("\n\n"));
+    out.push('\n');
+
+    let mut remaining = sections;
+    match issue_type {
+        "bug" if remaining > 0 => {
+            steps_to_reproduce(rng, &mut out);
+            remaining -= 1;
+        }
+        "user_story" if remaining > 0 => {
+            user_story(rng, &mut out);
+            remaining -= 1;
+        }
+        _ => {}
+    }
+
+    for _ in 0..remaining {
+        match SECTIONS[rng.gen_range(0..SECTIONS.len())] {
+            Section::Details => details(rng, &mut out),
+            Section::Notes => notes(rng, &mut out),
+            Section::AcceptanceCriteria => acceptance_criteria(rng, &mut out),
+            Section::Code => code(rng, &mut out),
+            Section::Links => links(rng, &mut out),
+        }
+    }
+
+    out
+}
+
+fn heading(out: &mut String, level: usize, title: &str) {
+    let _ = write!(out, "\n{} {}\n\n", "#".repeat(level), title);
+}
+
+fn sentence(rng: &mut StdRng) -> String {
+    Sentence(4..10).fake_with_rng(rng)
+}
+
+fn steps_to_reproduce(rng: &mut StdRng, out: &mut String) {
+    heading(out, 2, "Steps to reproduce");
+    for step in 1..=rng.gen_range(2..=6) {
+        let _ = writeln!(out, "{}. {}", step, sentence(rng));
+    }
+
+    heading(out, 3, "Expected behaviour");
+    let _ = writeln!(out, "{}", sentence(rng));
+
+    heading(out, 3, "Actual behaviour");
+    let _ = writeln!(out, "{}", sentence(rng));
+}
+
+fn user_story(rng: &mut StdRng, out: &mut String) {
+    heading(out, 2, "User story");
+    let role: String = BsNoun().fake_with_rng(rng);
+    let goal: String = Bs().fake_with_rng(rng);
+    let benefit: String = CatchPhase().fake_with_rng(rng);
+    let _ = writeln!(
+        out,
+        "As a member of the {} team, I want to {}, so that we get {}.",
+        role,
+        goal,
+        benefit.to_lowercase()
+    );
+    acceptance_criteria(rng, out);
+}
+
+fn details(rng: &mut StdRng, out: &mut String) {
+    let title: String = CatchPhase().fake_with_rng(rng);
+    heading(out, 2, &title);
+    let paragraphs: Vec<String> = Paragraphs(1..4).fake_with_rng(rng);
+    out.push_str(&paragraphs.join("\n\n"));
+    out.push('\n');
+}
+
+fn notes(rng: &mut StdRng, out: &mut String) {
+    heading(out, 2, "Notes");
+    for _ in 0..rng.gen_range(2..=6) {
+        let buzzword: String = Buzzword().fake_with_rng(rng);
+        let _ = writeln!(out, "- **{}**: {}", buzzword, sentence(rng));
+    }
+}
+
+fn acceptance_criteria(rng: &mut StdRng, out: &mut String) {
+    heading(out, 2, "Acceptance criteria");
+    for _ in 0..rng.gen_range(2..=5) {
+        let done = if rng.gen_bool(0.3) { "x" } else { " " };
+        let _ = writeln!(out, "- [{}] {}", done, sentence(rng));
+    }
+}
+
+fn code(rng: &mut StdRng, out: &mut String) {
+    let language = CODE_LANGUAGES[rng.gen_range(0..CODE_LANGUAGES.len())];
+    let words: Vec<String> = Words(3..6).fake_with_rng(rng);
+
+    heading(out, 2, "Example");
+    let _ = writeln!(out, "```{}", language);
+    match language {
+        "json" => {
+            let _ = writeln!(out, "{{");
+            for (index, word) in words.iter().enumerate() {
+                let separator = if index + 1 < words.len() { "," } else { "" };
+                let _ = writeln!(
+                    out,
+                    "  \"{}\": {}{}",
+                    word,
+                    rng.gen_range(0..1000),
+                    separator
+                );
+            }
+            let _ = writeln!(out, "}}");
+        }
+        "sh" => {
+            let _ = writeln!(out, "curl -s https://api.example.com/{}", words.join("/"));
+        }
+        "rust" => {
+            let _ = writeln!(out, "fn {}() -> Result<()> {{", words.join("_"));
+            let _ = writeln!(out, "    todo!(\"{}\")", sentence(rng));
+            let _ = writeln!(out, "}}");
+        }
+        _ => {
+            let _ = writeln!(
+                out,
+                "SELECT {} FROM {} LIMIT {};",
+                words[1..].join(", "),
+                words[0],
+                rng.gen_range(1..100)
+            );
+        }
+    }
+    let _ = writeln!(out, "```");
+}
+
+fn links(rng: &mut StdRng, out: &mut String) {
+    heading(out, 2, "Links");
+    for _ in 0..rng.gen_range(1..=4) {
+        let title: String = CatchPhase().fake_with_rng(rng);
+        let host: String = Word().fake_with_rng(rng);
+        let suffix: String = DomainSuffix().fake_with_rng(rng);
+        let path: Vec<String> = Words(1..3).fake_with_rng(rng);
+        let _ = writeln!(
+            out,
+            "- [{}](https://{}.{}/{})",
+            title,
+            host,
+            suffix,
+            path.join("/")
+        );
+    }
+}
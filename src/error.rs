@@ -1 +1,152 @@
+use reqwest::StatusCode;
+use std::fmt;
+
 pub type Result<T> = anyhow::Result<T>;
+
+/// Returned when the run completed but some SLO thresholds were not met
+#[derive(Debug)]
+pub struct ThresholdError {
+    pub failed: usize,
+}
+
+impl fmt::Display for ThresholdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} threshold(s) failed", self.failed)
+    }
+}
+
+impl std::error::Error for ThresholdError {}
+
+/// Returned when a compared run is worse than its baseline
+#[derive(Debug)]
+pub struct RegressionError {
+    pub regressions: usize,
+}
+
+impl fmt::Display for RegressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} regression(s) found", self.regressions)
+    }
+}
+
+impl std::error::Error for RegressionError {}
+
+const MAX_BODY_LENGTH: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FailureKind {
+    Timeout,
+    Connect,
+    Decode,
+    ClientError,
+    ServerError,
+    Other,
+}
+
+impl FailureKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FailureKind::Timeout => "timeout",
+            FailureKind::Connect => "connect error",
+            FailureKind::Decode => "decode error",
+            FailureKind::ClientError => "client error",
+            FailureKind::ServerError => "server error",
+            FailureKind::Other => "request error",
+        }
+    }
+}
+
+/// Details of a failed request, kept for the error summary
+#[derive(Clone, Debug)]
+pub struct RequestError {
+    pub kind: FailureKind,
+    pub status: Option<u16>,
+    pub message: String,
+    /// Response body truncated to a few hundred characters
+    pub body: Option<String>,
+}
+
+impl RequestError {
+    pub fn from_status(status: StatusCode, body: &[u8]) -> RequestError {
+        let kind = if status.is_server_error() {
+            FailureKind::ServerError
+        } else if status.is_client_error() {
+            FailureKind::ClientError
+        } else {
+            FailureKind::Other
+        };
+
+        RequestError {
+            kind,
+            status: Some(status.as_u16()),
+            message: status.to_string(),
+            body: truncate_body(body),
+        }
+    }
+
+    pub fn decode(status: StatusCode, err: &serde_json::Error, body: &[u8]) -> RequestError {
+        RequestError {
+            kind: FailureKind::Decode,
+            status: Some(status.as_u16()),
+            message: err.to_string(),
+            body: truncate_body(body),
+        }
+    }
+
+    pub fn from_reqwest(err: &reqwest::Error) -> RequestError {
+        let kind = if err.is_timeout() {
+            FailureKind::Timeout
+        } else if err.is_connect() {
+            FailureKind::Connect
+        } else if err.is_decode() || err.is_body() {
+            FailureKind::Decode
+        } else {
+            FailureKind::Other
+        };
+
+        RequestError {
+            kind,
+            status: err.status().map(|status| status.as_u16()),
+            message: err.to_string(),
+            body: None,
+        }
+    }
+
+    /// Grouping key for the error summary, HTTP errors are grouped by status code
+    pub fn group(&self) -> String {
+        match (self.kind, self.status) {
+            (FailureKind::ClientError | FailureKind::ServerError, Some(status)) => {
+                status.to_string()
+            }
+            _ => self.kind.label().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.label(), self.message)?;
+        if let Some(body) = &self.body {
+            write!(f, " - {}", body)?;
+        }
+        Ok(())
+    }
+}
+
+fn truncate_body(body: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut truncated: String = text
+        .chars()
+        .take(MAX_BODY_LENGTH)
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    if text.chars().count() > MAX_BODY_LENGTH {
+        truncated.push_str("...");
+    }
+    Some(truncated)
+}
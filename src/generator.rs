@@ -0,0 +1,220 @@
+use fake::faker::company::en::CatchPhase;
+use fake::faker::lorem::en::Paragraphs;
+use fake::Fake;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+
+use crate::config::{CommentConfig, DescriptionConfig, WeightedCount};
+use crate::description;
+use crate::model::{CreateCommentBody, CreateIssueBody, Issue, IssueStatus, Label, ProjectMember};
+
+/// Comment to post once its issue is created
+pub struct NewComment {
+    pub body: CreateCommentBody,
+    /// Author's token from the pool, the configured token when None
+    pub token: Option<String>,
+}
+
+/// Builds fake issue payloads from the project's existing labels, members, statuses and parents.
+/// All randomness comes from one seeded RNG, so the same seed and project state
+/// yields the same payloads in the same order.
+pub struct IssueGenerator {
+    pub issue_type: String,
+    /// Weighted issue types, `issue_type` is used when empty
+    pub issue_mix: Vec<(String, u32)>,
+    /// Weighted number of markdown sections in descriptions
+    pub description_sections: Vec<WeightedCount>,
+    pub estimate_type: String,
+    pub labels: Vec<Label>,
+    pub statuses: Vec<IssueStatus>,
+    pub initiatives: Vec<Issue>,
+    pub epics: Vec<Issue>,
+    pub members: Vec<ProjectMember>,
+    rng: Mutex<StdRng>,
+}
+
+impl IssueGenerator {
+    pub fn new(
+        issue_type: String,
+        estimate_type: String,
+        labels: Vec<Label>,
+        statuses: Vec<IssueStatus>,
+        members: Vec<ProjectMember>,
+        seed: u64,
+    ) -> IssueGenerator {
+        IssueGenerator {
+            issue_type,
+            issue_mix: Vec::new(),
+            description_sections: DescriptionConfig::default().sections,
+            estimate_type,
+            labels,
+            statuses,
+            initiatives: Vec::new(),
+            epics: Vec::new(),
+            members,
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    pub fn generate(&self) -> CreateIssueBody {
+        let issue_type = self.pick_issue_type();
+        self.generate_for(issue_type)
+    }
+
+    /// Comments for one issue, as many as the weighted distribution picks
+    pub fn generate_comments(&self, config: &CommentConfig) -> Vec<NewComment> {
+        if config.per_issue.is_empty() {
+            return Vec::new();
+        }
+
+        let mut rng = self.rng.lock().unwrap();
+        let rng = &mut *rng;
+        let count = pick_count(rng, &config.per_issue);
+
+        (0..count)
+            .map(|_| {
+                let paragraphs: Vec<String> = Paragraphs(1..4).fake_with_rng(rng);
+                NewComment {
+                    body: CreateCommentBody {
+                        body: paragraphs.join("\n\n"),
+                    },
+                    token: get_random_item(rng, &config.tokens, 100).cloned(),
+                }
+            })
+            .collect()
+    }
+
+    /// Picks a type from the mix proportionally to its weight
+    fn pick_issue_type(&self) -> &str {
+        let total: u32 = self.issue_mix.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return self.issue_type.as_str();
+        }
+
+        let mut roll = self.rng.lock().unwrap().gen_range(0..total);
+        for (issue_type, weight) in self.issue_mix.iter() {
+            if roll < *weight {
+                return issue_type.as_str();
+            }
+            roll -= weight;
+        }
+        self.issue_type.as_str()
+    }
+
+    /// Payload for the given type, with only the fields that type supports
+    pub fn generate_for(&self, issue_type: &str) -> CreateIssueBody {
+        let hours: Vec<u32> = (1..=20).collect();
+        let points = vec![1, 2, 3, 5, 8, 13, 21];
+        let mut rng = self.rng.lock().unwrap();
+        let rng = &mut *rng;
+
+        let member = get_random_item(rng, &self.members, 30);
+        let label = get_random_item(rng, &self.labels, 30);
+
+        let mut initiative: Option<&Issue> = None;
+        let mut epic: Option<&Issue> = None;
+        let mut status: Option<&IssueStatus> = None;
+
+        // Initiatives and epics do not have these properties
+        match issue_type {
+            "initiative" => {
+                // Do nothing...
+            }
+            "epic" => {
+                initiative = get_random_item(rng, &self.initiatives, 20);
+            }
+            _ => {
+                epic = get_random_item(rng, &self.epics, 20);
+                status = get_random_item(rng, &self.statuses, 100);
+            }
+        };
+
+        let default_labels: Vec<String> = vec![];
+
+        let title: String = CatchPhase().fake_with_rng(rng);
+        let sections = pick_count(rng, &self.description_sections);
+        let description = description::generate(rng, issue_type, sections);
+
+        let mut payload = CreateIssueBody {
+            r#type: issue_type.to_string(),
+            initiative_id: None,
+            epic_id: None,
+            parent_id: None,
+            assignee_id: None,
+            title,
+            description: Some(description),
+            estimate_type: Some(self.estimate_type.clone()),
+            estimate: Some(10),
+            status: None,
+            labels: default_labels,
+        };
+
+        if self.estimate_type == "points" {
+            let estimate = get_random_item(rng, &points, 100);
+            payload.estimate = Some(*estimate.unwrap());
+        } else {
+            let estimate = get_random_item(rng, &hours, 100);
+            payload.estimate = Some(*estimate.unwrap());
+        }
+
+        if let Some(initiative_value) = initiative {
+            payload.initiative_id = Some(String::from(initiative_value.id.as_str()));
+        }
+
+        if let Some(epic_value) = epic {
+            payload.epic_id = Some(String::from(epic_value.id.as_str()));
+        }
+        if let Some(member_value) = member {
+            if let Some(user_value) = &member_value.user {
+                payload.assignee_id = Some(String::from(user_value.id.as_str()));
+            }
+        }
+        if let Some(status_value) = status {
+            payload.status = Some(String::from(status_value.id.as_str()));
+        }
+        if let Some(label_value) = label {
+            payload.labels = vec![String::from(label_value.id.as_str())];
+        }
+
+        payload
+    }
+}
+
+/// Picks a count proportionally to the weights, 0 when every weight is 0
+fn pick_count(rng: &mut StdRng, counts: &[WeightedCount]) -> u32 {
+    let total: u32 = counts.iter().map(|count| count.weight).sum();
+    if total == 0 {
+        return 0;
+    }
+
+    let mut roll = rng.gen_range(0..total);
+    for count in counts.iter() {
+        if roll < count.weight {
+            return count.count;
+        }
+        roll -= count.weight;
+    }
+    0
+}
+
+fn get_item_chance(rng: &mut StdRng, chance: u32) -> bool {
+    if chance > 100 {
+        panic!("Chance must be between 0 to 100")
+    }
+
+    let value = rng.gen_range(0..=100);
+    value <= chance
+}
+
+fn get_random_item<'a, T>(rng: &mut StdRng, items: &'a [T], chance: u32) -> Option<&'a T> {
+    let length = items.len();
+    let return_item = get_item_chance(rng, chance);
+
+    if length > 0 && return_item {
+        let max_length = length - 1;
+        let key = rng.gen_range(0..=max_length);
+        return items.get(key);
+    }
+    None
+}
@@ -0,0 +1,425 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::report::{DistributionBucket, LatencyReport, RunReport, TimelinePoint};
+
+const CHART_WIDTH: f64 = 760.0;
+const CHART_HEIGHT: f64 = 260.0;
+/// Space for the axis labels around the plot area
+const MARGIN_LEFT: f64 = 64.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 16.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+const GRID_LINES: usize = 4;
+
+const STYLE: &str = "body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;margin:2rem auto;max-width:960px;color:#1f2328;padding:0 1rem}\
+h1{font-size:1.6rem}h2{font-size:1.2rem;margin-top:2rem;border-bottom:1px solid #d0d7de;padding-bottom:.3rem}\
+table{border-collapse:collapse;font-size:.9rem;margin:.5rem 0}th,td{border:1px solid #d0d7de;padding:.3rem .6rem}\
+th{background:#f6f8fa;text-align:left}td.num{text-align:right;font-variant-numeric:tabular-nums}\
+.grid{display:grid;grid-template-columns:repeat(auto-fill,minmax(150px,1fr));gap:.6rem}\
+.card{border:1px solid #d0d7de;border-radius:6px;padding:.6rem}.card .label{color:#59636e;font-size:.8rem}\
+.card .value{font-size:1.3rem;font-weight:600}.bad{color:#cf222e}\
+svg text{font-size:11px;fill:#59636e}.legend span{display:inline-block;margin-right:1rem;font-size:.85rem}\
+.legend i{display:inline-block;width:12px;height:3px;margin-right:4px;vertical-align:middle}";
+
+/// Line colors, one per series
+const COLORS: [&str; 4] = ["#0969da", "#bf8700", "#cf222e", "#8250df"];
+
+/// Chart series label and its (x, y) points
+type Series<'a> = (&'a str, Vec<(f64, f64)>);
+
+impl RunReport {
+    /// Writes a single static HTML file with inline styles and SVG charts
+    pub fn write_html(&self, filename: &Path) -> Result<()> {
+        fs::write(filename, render(self))?;
+        println!("HTML report written to {}", filename.display());
+        Ok(())
+    }
+}
+
+fn render(report: &RunReport) -> String {
+    let mut out = String::new();
+    let title = format!("{} run report", report.command);
+
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(out, "<title>{}</title>", escape(&title));
+    let _ = writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(out, "<h1>{}</h1>", escape(&title));
+    let _ = writeln!(
+        out,
+        "<p>Started {} and finished {}</p>",
+        escape(&report.started_at),
+        escape(&report.finished_at)
+    );
+
+    render_cards(&mut out, report);
+
+    out.push_str("<h2>Latency</h2>\n");
+    let mut latencies: Vec<(&str, &LatencyReport)> = vec![("Service time", &report.latency)];
+    if let Some(response_time) = &report.response_time {
+        latencies.push(("Response time (from intended send time)", response_time));
+    }
+    if let Some(page_load) = &report.page_load {
+        latencies.push(("Page load", &page_load.latency));
+    }
+    render_latency_table(&mut out, &latencies);
+
+    if !report.distribution.is_empty() {
+        out.push_str("<h2>Latency distribution</h2>\n");
+        out.push_str(&histogram_svg(&report.distribution));
+    }
+
+    if !report.timeline.is_empty() {
+        render_timeline(&mut out, &report.timeline);
+    }
+
+    if !report.endpoints.is_empty() {
+        out.push_str("<h2>Endpoints</h2>\n<table>\n");
+        out.push_str(
+            "<tr><th>Endpoint</th><th>Count</th><th>Errors</th><th>p50 ms</th><th>p90 ms</th>\
+             <th>p95 ms</th><th>p99 ms</th><th>Max ms</th><th>Req/s</th></tr>\n",
+        );
+        for endpoint in report.endpoints.iter() {
+            let latency = &endpoint.latency;
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td>{}{}{}{}{}{}{}{}</tr>",
+                escape(&endpoint.name),
+                num(endpoint.count),
+                num(endpoint.errors),
+                ms(latency.p50_ms),
+                ms(latency.p90_ms),
+                ms(latency.p95_ms),
+                ms(latency.p99_ms),
+                ms(latency.max_ms),
+                num(endpoint.rps)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    if !report.stages.is_empty() {
+        out.push_str("<h2>Stages</h2>\n<table>\n");
+        out.push_str(
+            "<tr><th>Stage</th><th>Duration ms</th><th>Target/s</th><th>Count</th><th>Errors</th>\
+             <th>Queued</th><th>p50 ms</th><th>p95 ms</th><th>p99 ms</th><th>Req/s</th></tr>\n",
+        );
+        for (index, stage) in report.stages.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "<tr>{}{}{}{}{}{}{}{}{}{}</tr>",
+                num(index + 1),
+                num(stage.duration_ms),
+                num(stage.target_rps),
+                num(stage.count),
+                num(stage.errors),
+                num(stage.queued),
+                ms(stage.latency.p50_ms),
+                ms(stage.latency.p95_ms),
+                ms(stage.latency.p99_ms),
+                num(stage.rps)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    if !report.errors.is_empty() {
+        out.push_str("<h2>Errors</h2>\n<table>\n");
+        out.push_str("<tr><th>Group</th><th>Count</th><th>Sample</th></tr>\n");
+        for errors in report.errors.iter() {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td>{}<td><code>{}</code></td></tr>",
+                escape(&errors.group),
+                num(errors.count),
+                escape(&errors.sample)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    if let Some(config) = report.config.as_object() {
+        out.push_str("<h2>Configuration</h2>\n<table>\n");
+        for (key, value) in config.iter() {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "<tr><th>{}</th><td><code>{}</code></td></tr>",
+                escape(key),
+                escape(&value)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn render_cards(out: &mut String, report: &RunReport) {
+    let success_rate = match report.success_rate {
+        Some(rate) => format!("{}%", rate),
+        None => String::from("n/a"),
+    };
+    let mut cards: Vec<(&str, String, bool)> = vec![
+        ("Total requests", report.total.to_string(), false),
+        ("Succeed", report.succeed.to_string(), false),
+        ("Failed", report.failed.to_string(), report.failed > 0),
+        ("Success rate", success_rate, report.failed > 0),
+        ("Requests per second", report.rps.to_string(), false),
+        ("p95", format!("{:.3} ms", report.latency.p95_ms), false),
+        ("Retries", report.retries.to_string(), false),
+        (
+            "Run duration",
+            format!("{:.1} s", report.run_duration_ms / 1000.0),
+            false,
+        ),
+    ];
+    if report.queued > 0 {
+        cards.push(("Queued", report.queued.to_string(), true));
+    }
+
+    out.push_str("<div class=\"grid\">\n");
+    for (label, value, bad) in cards {
+        let _ = writeln!(
+            out,
+            "<div class=\"card\"><div class=\"label\">{}</div><div class=\"value{}\">{}</div></div>",
+            label,
+            if bad { " bad" } else { "" },
+            escape(&value)
+        );
+    }
+    out.push_str("</div>\n");
+}
+
+fn render_latency_table(out: &mut String, latencies: &[(&str, &LatencyReport)]) {
+    out.push_str("<table>\n<tr><th></th>");
+    if let Some((_, latency)) = latencies.first() {
+        for (metric, _) in latency.fields() {
+            let _ = write!(out, "<th>{}</th>", metric);
+        }
+    }
+    out.push_str("</tr>\n");
+    for (label, latency) in latencies {
+        let _ = write!(out, "<tr><th>{}</th>", escape(label));
+        for (_, value) in latency.fields() {
+            out.push_str(&ms(value));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+}
+
+fn render_timeline(out: &mut String, timeline: &[TimelinePoint]) {
+    let point = |value: fn(&TimelinePoint) -> f64| -> Vec<(f64, f64)> {
+        timeline
+            .iter()
+            .map(|p| (p.offset_s as f64, value(p)))
+            .collect()
+    };
+
+    out.push_str("<h2>Latency over time</h2>\n");
+    out.push_str(&line_chart_svg(
+        &[
+            ("p50", point(|p| p.p50_ms)),
+            ("p95", point(|p| p.p95_ms)),
+            ("p99", point(|p| p.p99_ms)),
+        ],
+        "ms",
+    ));
+
+    out.push_str("<h2>Throughput over time</h2>\n");
+    out.push_str(&line_chart_svg(
+        &[
+            ("requests/s", point(|p| p.rps)),
+            ("errors/s", point(|p| p.errors as f64 / p.interval_s as f64)),
+        ],
+        "req/s",
+    ));
+}
+
+fn histogram_svg(buckets: &[DistributionBucket]) -> String {
+    let max_count = buckets.iter().map(|bucket| bucket.count).max().unwrap_or(0);
+    let plot_width = CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = CHART_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let bar_width = plot_width / buckets.len() as f64;
+
+    let mut svg = svg_open();
+    svg_grid(&mut svg, max_count as f64, "count");
+
+    for (index, bucket) in buckets.iter().enumerate() {
+        let height = if max_count == 0 {
+            0.0
+        } else {
+            bucket.count as f64 / max_count as f64 * plot_height
+        };
+        let x = MARGIN_LEFT + bar_width * index as f64;
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>&lt;= {:.3} ms: {}</title></rect>",
+            x + 1.0,
+            MARGIN_TOP + plot_height - height,
+            (bar_width - 2.0).max(1.0),
+            height,
+            COLORS[0],
+            bucket.le_ms,
+            bucket.count
+        );
+        // Label every other bucket to keep the axis readable
+        if index % 2 == 1 || buckets.len() <= 10 {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+                x + bar_width / 2.0,
+                CHART_HEIGHT - MARGIN_BOTTOM + 16.0,
+                short_number(bucket.le_ms)
+            );
+        }
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">latency ms (bucket upper bound)</text>",
+        MARGIN_LEFT + plot_width / 2.0,
+        CHART_HEIGHT - 6.0
+    );
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn line_chart_svg(series: &[Series], y_label: &str) -> String {
+    let max_x = series
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|(x, _)| *x))
+        .fold(0.0, f64::max)
+        .max(1.0);
+    let max_y = series
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|(_, y)| *y))
+        .fold(0.0, f64::max);
+    let plot_width = CHART_WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = CHART_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let scale_y = if max_y > 0.0 { max_y } else { 1.0 };
+
+    let mut svg = svg_open();
+    svg_grid(&mut svg, max_y, y_label);
+
+    for step in 0..=GRID_LINES {
+        let value = max_x * step as f64 / GRID_LINES as f64;
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            MARGIN_LEFT + plot_width * step as f64 / GRID_LINES as f64,
+            CHART_HEIGHT - MARGIN_BOTTOM + 16.0,
+            short_number(value)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">seconds since start</text>",
+        MARGIN_LEFT + plot_width / 2.0,
+        CHART_HEIGHT - 6.0
+    );
+
+    let mut legend = String::from("<div class=\"legend\">");
+    for (index, (label, points)) in series.iter().enumerate() {
+        let color = COLORS[index % COLORS.len()];
+        let path: Vec<String> = points
+            .iter()
+            .map(|(x, y)| {
+                format!(
+                    "{:.1},{:.1}",
+                    MARGIN_LEFT + x / max_x * plot_width,
+                    MARGIN_TOP + plot_height - y / scale_y * plot_height
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>",
+            color,
+            path.join(" ")
+        );
+        let _ = write!(
+            legend,
+            "<span><i style=\"background:{}\"></i>{}</span>",
+            color,
+            escape(label)
+        );
+    }
+    legend.push_str("</div>\n");
+
+    svg.push_str("</svg>\n");
+    svg.push_str(&legend);
+    svg
+}
+
+fn svg_open() -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"{w}\" height=\"{h}\">\n",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT
+    )
+}
+
+/// Horizontal grid lines with their y axis values
+fn svg_grid(svg: &mut String, max_y: f64, y_label: &str) {
+    let plot_height = CHART_HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    for step in 0..=GRID_LINES {
+        let y = MARGIN_TOP + plot_height - plot_height * step as f64 / GRID_LINES as f64;
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#d0d7de\"/>",
+            MARGIN_LEFT,
+            y,
+            CHART_WIDTH - MARGIN_RIGHT,
+            y
+        );
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            MARGIN_LEFT - 6.0,
+            y + 4.0,
+            short_number(max_y * step as f64 / GRID_LINES as f64)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<text x=\"12\" y=\"{:.1}\" text-anchor=\"middle\" transform=\"rotate(-90 12 {:.1})\">{}</text>",
+        MARGIN_TOP + plot_height / 2.0,
+        MARGIN_TOP + plot_height / 2.0,
+        escape(y_label)
+    );
+}
+
+fn num<T: std::fmt::Display>(value: T) -> String {
+    format!("<td class=\"num\">{}</td>", value)
+}
+
+fn ms(value: f64) -> String {
+    format!("<td class=\"num\">{:.3}</td>", value)
+}
+
+/// Axis labels without needless decimals
+fn short_number(value: f64) -> String {
+    if value >= 100.0 || value == value.trunc() {
+        format!("{:.0}", value)
+    } else if value >= 10.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
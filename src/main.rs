@@ -4,26 +4,77 @@ use config::Commands;
 use config::Config;
 use std::process;
 
-use crate::error::Result;
+use crate::error::{RegressionError, Result, ThresholdError};
 
+pub mod compare;
 pub mod config;
 pub mod crawler;
+pub mod description;
 pub mod error;
+pub mod generator;
+pub mod html;
+pub mod metrics;
 pub mod model;
+pub mod pool;
+pub mod progress;
+pub mod report;
+pub mod retry;
 pub mod run;
+pub mod scenario;
+pub mod schedule;
+pub mod stats;
+pub mod thresholds;
+pub mod timeseries;
+
+/// Exit code when the run completed but failed its SLO thresholds
+const THRESHOLD_EXIT_CODE: i32 = 2;
+
+/// Exit code when a compared run regressed against its baseline
+const REGRESSION_EXIT_CODE: i32 = 3;
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
-    let config = Config::build(args.config.as_path()).unwrap_or_else(|err| {
+
+    // Comparing saved reports does not need a config file
+    let result = match &args.command {
+        Commands::Compare(compare_args) => compare::compare(compare_args),
+        _ => {
+            let config = load_config(&args);
+            run_command(args, config).await
+        }
+    };
+
+    if let Err(e) = result {
+        if e.downcast_ref::<ThresholdError>().is_some() {
+            eprintln!("Threshold error: {e}");
+            process::exit(THRESHOLD_EXIT_CODE);
+        }
+        if e.downcast_ref::<RegressionError>().is_some() {
+            eprintln!("Regression error: {e}");
+            process::exit(REGRESSION_EXIT_CODE);
+        }
+        eprintln!("Application error: {e}");
+        process::exit(1);
+    }
+}
+
+fn load_config(args: &Args) -> Config {
+    let Some(filename) = &args.config else {
+        eprintln!("A config file is required for this command.");
+        process::exit(1);
+    };
+
+    let mut config = Config::build(filename.as_path()).unwrap_or_else(|err| {
         eprintln!("{err}");
         process::exit(1);
     });
 
-    if let Err(e) = run_command(args, config).await {
-        eprintln!("Application error: {e}");
+    config.apply_args(args).unwrap_or_else(|err| {
+        eprintln!("{err}");
         process::exit(1);
-    }
+    });
+    config
 }
 
 async fn run_command(args: Args, config: Config) -> Result<()> {
@@ -40,5 +91,18 @@ async fn run_command(args: Args, config: Config) -> Result<()> {
             run::crawl_all_projects_issues(config).await?;
             Ok(())
         }
+        Commands::CrawlIssuePages => {
+            run::crawl_issue_pages(config).await?;
+            Ok(())
+        }
+        Commands::Compare(_) => unreachable!("compare runs without a config in main"),
+        Commands::Seed(seed_args) => {
+            run::seed(config, &seed_args).await?;
+            Ok(())
+        }
+        Commands::Scenario(scenario_args) => {
+            run::run_scenario(config, &scenario_args).await?;
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,181 @@
+use std::fmt::Write as _;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::config::Config;
+use crate::crawler::ApiClient;
+use crate::error::Result;
+use crate::stats::{RunStats, SharedStats};
+
+/// Upper bounds of the latency histogram buckets in seconds, same as the Prometheus client defaults
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Largest request accepted from a scraper, only the request line is used
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+/// Serves the live metrics in the Prometheus text format, stops when dropped
+pub struct MetricsServer {
+    handle: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// Starts listening when a metrics address is configured
+    pub async fn start(config: &Config, client: &ApiClient) -> Result<Option<MetricsServer>> {
+        let (Some(addr), Some(live_stats)) = (config.metrics_listen, client.live_stats()) else {
+            return Ok(None);
+        };
+
+        let listener = TcpListener::bind(addr).await?;
+        println!(
+            "Serving metrics on http://{}/metrics",
+            listener.local_addr()?
+        );
+
+        let live_stats = live_stats.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    continue;
+                };
+                let live_stats = live_stats.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = handle_connection(stream, &live_stats).await {
+                        eprintln!("Unable to serve metrics. Error: {}", err);
+                    }
+                });
+            }
+        });
+
+        Ok(Some(MetricsServer { handle }))
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, live_stats: &SharedStats) -> Result<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 || buffer.len() > MAX_REQUEST_SIZE {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&buffer);
+    let mut request_line = request
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+
+    let (status, body) = match (method, path) {
+        ("GET", "/metrics") => {
+            let body = render(&live_stats.lock());
+            ("200 OK", body)
+        }
+        _ => ("404 Not Found", String::from("Not found\n")),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+/// Renders the stats collected since the start of the run
+fn render(stats: &RunStats) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP issue_creator_requests_total Completed requests per endpoint.\n");
+    out.push_str("# TYPE issue_creator_requests_total counter\n");
+    for (name, endpoint) in stats.endpoints.iter() {
+        let _ = writeln!(
+            out,
+            "issue_creator_requests_total{{endpoint=\"{}\"}} {}",
+            name, endpoint.total
+        );
+    }
+
+    out.push_str(
+        "# HELP issue_creator_request_errors_total Failed requests per endpoint by status code or failure kind.\n",
+    );
+    out.push_str("# TYPE issue_creator_request_errors_total counter\n");
+    for (name, endpoint) in stats.endpoints.iter() {
+        for (group, count) in endpoint.errors.iter() {
+            let _ = writeln!(
+                out,
+                "issue_creator_request_errors_total{{endpoint=\"{}\",status=\"{}\"}} {}",
+                name, group, count
+            );
+        }
+    }
+
+    out.push_str("# HELP issue_creator_responses_total Responses by HTTP status code.\n");
+    out.push_str("# TYPE issue_creator_responses_total counter\n");
+    for (status, count) in stats.statuses.iter() {
+        let _ = writeln!(
+            out,
+            "issue_creator_responses_total{{status=\"{}\"}} {}",
+            status, count
+        );
+    }
+
+    out.push_str("# HELP issue_creator_retries_total Retried attempts of all requests.\n");
+    out.push_str("# TYPE issue_creator_retries_total counter\n");
+    let _ = writeln!(out, "issue_creator_retries_total {}", stats.retries);
+
+    out.push_str(
+        "# HELP issue_creator_request_duration_seconds Request latency per endpoint, final attempt only without retries.\n",
+    );
+    out.push_str("# TYPE issue_creator_request_duration_seconds histogram\n");
+    for (name, endpoint) in stats.endpoints.iter() {
+        let latencies = &endpoint.latencies;
+        for bucket in BUCKETS {
+            let _ = writeln!(
+                out,
+                "issue_creator_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"{}\"}} {}",
+                name,
+                bucket,
+                latencies.count_at_most(Duration::from_secs_f64(bucket))
+            );
+        }
+        let _ = writeln!(
+            out,
+            "issue_creator_request_duration_seconds_bucket{{endpoint=\"{}\",le=\"+Inf\"}} {}",
+            name,
+            latencies.len()
+        );
+        let _ = writeln!(
+            out,
+            "issue_creator_request_duration_seconds_sum{{endpoint=\"{}\"}} {}",
+            name,
+            latencies.mean().as_secs_f64() * latencies.len() as f64
+        );
+        let _ = writeln!(
+            out,
+            "issue_creator_request_duration_seconds_count{{endpoint=\"{}\"}} {}",
+            name,
+            latencies.len()
+        );
+    }
+
+    out
+}
@@ -0,0 +1,86 @@
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::{sleep_until, Instant};
+
+/// Runs tasks with at most `concurrency` of them in flight at any time.
+pub struct WorkerPool<T> {
+    semaphore: Arc<Semaphore>,
+    tasks: JoinSet<T>,
+}
+
+impl<T: Send + 'static> WorkerPool<T> {
+    pub fn new(concurrency: usize) -> WorkerPool<T> {
+        WorkerPool {
+            semaphore: Arc::new(Semaphore::new(concurrency)),
+            tasks: JoinSet::new(),
+        }
+    }
+
+    /// Waits for a free slot then spawns the task
+    pub async fn spawn<F>(&mut self, task: F)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let permit = self
+            .semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Worker pool semaphore closed");
+
+        self.tasks.spawn(async move {
+            let result = task.await;
+            drop(permit);
+            result
+        });
+    }
+
+    /// Spawns the task without waiting, it starts once a slot is free.
+    /// Returns true when every slot was busy and the task had to be queued.
+    pub fn queue<F>(&mut self, task: F) -> bool
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (permit, queued) = match self.semaphore.clone().try_acquire_owned() {
+            Ok(permit) => (Some(permit), false),
+            Err(_) => (None, true),
+        };
+
+        let semaphore = self.semaphore.clone();
+        self.tasks.spawn(async move {
+            let permit = match permit {
+                Some(permit) => permit,
+                None => semaphore
+                    .acquire_owned()
+                    .await
+                    .expect("Worker pool semaphore closed"),
+            };
+            let result = task.await;
+            drop(permit);
+            result
+        });
+        queued
+    }
+
+    /// Hands over finished tasks to `collect` until the deadline is reached,
+    /// tasks that are already done are collected even when the deadline has passed
+    pub async fn join_until<C>(&mut self, deadline: Instant, mut collect: C)
+    where
+        C: FnMut(T),
+    {
+        loop {
+            tokio::select! {
+                biased;
+                Some(res) = self.tasks.join_next() => collect(res.unwrap()),
+                _ = sleep_until(deadline) => return,
+            }
+        }
+    }
+
+    /// Waits for the next finished task, None when all tasks are done
+    pub async fn join_next(&mut self) -> Option<T> {
+        self.tasks.join_next().await.map(|res| res.unwrap())
+    }
+}
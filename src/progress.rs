@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use crate::crawler::ResponseData;
+use crate::stats::format_ms;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Recent requests used for the current rate and rolling p95
+const WINDOW: Duration = Duration::from_secs(5);
+
+/// Live counters of the running command, shared by every clone of the client
+#[derive(Clone, Debug)]
+pub struct Progress {
+    state: Arc<ProgressState>,
+}
+
+#[derive(Debug)]
+struct ProgressState {
+    started: Instant,
+    completed: AtomicU64,
+    errors: AtomicU64,
+    /// Expected number of requests, zero when unknown
+    total: AtomicU64,
+    deadline: Mutex<Option<Instant>>,
+    window: Mutex<VecDeque<(Instant, Duration)>>,
+    /// Whether the status line is currently drawn on the terminal
+    active: AtomicBool,
+}
+
+/// Background task redrawing the status line until stopped
+pub struct ProgressDisplay {
+    progress: Progress,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            state: Arc::new(ProgressState {
+                started: Instant::now(),
+                completed: AtomicU64::new(0),
+                errors: AtomicU64::new(0),
+                total: AtomicU64::new(0),
+                deadline: Mutex::new(None),
+                window: Mutex::new(VecDeque::new()),
+                active: AtomicBool::new(false),
+            }),
+        }
+    }
+}
+
+impl Progress {
+    pub fn record<T>(&self, res: &ResponseData<T>) {
+        self.state.completed.fetch_add(1, Ordering::Relaxed);
+        if res.data.is_none() {
+            self.state.errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let now = Instant::now();
+        let mut window = self.state.window.lock().unwrap();
+        window.push_back((now, res.duration));
+        prune(&mut window, now);
+    }
+
+    /// Adds requests that are now known to be part of the run
+    pub fn add_total(&self, count: u64) {
+        self.state.total.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Runs bound to a duration estimate the remaining time from the deadline
+    pub fn set_deadline(&self, duration: Duration) {
+        *self.state.deadline.lock().unwrap() = Some(Instant::now() + duration);
+    }
+
+    /// Starts refreshing the status line every second, only when stderr is a terminal
+    pub fn display(&self) -> ProgressDisplay {
+        if !std::io::stderr().is_terminal() {
+            return ProgressDisplay {
+                progress: self.clone(),
+                handle: None,
+            };
+        }
+
+        self.state.active.store(true, Ordering::Relaxed);
+        let progress = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let mut stderr = std::io::stderr().lock();
+                let _ = write!(stderr, "\r\x1b[2K{}", progress.status_line());
+                let _ = stderr.flush();
+            }
+        });
+
+        ProgressDisplay {
+            progress: self.clone(),
+            handle: Some(handle),
+        }
+    }
+
+    /// Prints a line to stdout without getting mixed up with the status line
+    pub fn println(&self, line: &str) {
+        self.print_above(|| println!("{}", line));
+    }
+
+    /// Prints a line to stderr without getting mixed up with the status line
+    pub fn eprintln(&self, line: &str) {
+        self.print_above(|| eprintln!("{}", line));
+    }
+
+    /// Clears the status line, prints, then draws the status line again below
+    fn print_above<F: FnOnce()>(&self, print: F) {
+        let active = self.state.active.load(Ordering::Relaxed);
+        if active {
+            eprint!("\r\x1b[2K");
+        }
+        print();
+        if active {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "{}", self.status_line());
+            let _ = stderr.flush();
+        }
+    }
+
+    fn status_line(&self) -> String {
+        let state = &self.state;
+        let elapsed = state.started.elapsed();
+        let completed = state.completed.load(Ordering::Relaxed);
+        let errors = state.errors.load(Ordering::Relaxed);
+        let total = state.total.load(Ordering::Relaxed);
+
+        let (rate, p95) = {
+            let now = Instant::now();
+            let mut window = state.window.lock().unwrap();
+            prune(&mut window, now);
+            let mut durations: Vec<Duration> = window.iter().map(|(_, d)| *d).collect();
+            durations.sort();
+            let p95 = durations
+                .get((durations.len() * 95).div_ceil(100).saturating_sub(1))
+                .copied();
+            (
+                durations.len() as f64 / WINDOW.as_secs_f64().min(elapsed.as_secs_f64()),
+                p95,
+            )
+        };
+
+        let mut parts: Vec<String> = vec![format!("[{}]", format_clock(elapsed))];
+        if total > 0 {
+            let total = total.max(completed);
+            parts.push(format!(
+                "{}/{} ({}%)",
+                completed,
+                total,
+                completed * 100 / total
+            ));
+        } else {
+            parts.push(format!("{} done", completed));
+        }
+        parts.push(format!("{:.1} req/s", rate));
+        if let Some(p95) = p95 {
+            parts.push(format!("p95 {}", format_ms(p95)));
+        }
+        parts.push(format!("errors {}", errors));
+
+        let deadline = *state.deadline.lock().unwrap();
+        let eta = match deadline {
+            Some(deadline) => Some(deadline.saturating_duration_since(Instant::now())),
+            None if total > completed && completed > 0 => {
+                let remaining = (total - completed) as f64;
+                let average = completed as f64 / elapsed.as_secs_f64();
+                Some(Duration::from_secs_f64(remaining / average))
+            }
+            None => None,
+        };
+        if let Some(eta) = eta {
+            parts.push(format!("ETA {}", format_clock(eta)));
+        }
+
+        parts.join("  ")
+    }
+}
+
+impl ProgressDisplay {
+    /// Stops refreshing and clears the status line
+    pub fn stop(self) {
+        if let Some(handle) = self.handle {
+            handle.abort();
+            self.progress.state.active.store(false, Ordering::Relaxed);
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+fn prune(window: &mut VecDeque<(Instant, Duration)>, now: Instant) {
+    while let Some((at, _)) = window.front() {
+        if now.duration_since(*at) <= WINDOW {
+            break;
+        }
+        window.pop_front();
+    }
+}
+
+/// Formats as mm:ss, or hh:mm:ss for an hour or longer
+fn format_clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
@@ -0,0 +1,548 @@
+use bigdecimal::ToPrimitive;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::stats::{round_ms, throughput, LatencyHistogram, RunStats, Sample, TimelineBucket};
+
+/// Buckets of the latency distribution in reports
+const DISTRIBUTION_BUCKETS: u64 = 20;
+
+/// Adjacent seconds are combined so long runs stay below this many timeline points
+const MAX_TIMELINE_POINTS: u64 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+    pub stdev_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub p999_ms: f64,
+}
+
+impl From<&LatencyHistogram> for LatencyReport {
+    fn from(latencies: &LatencyHistogram) -> Self {
+        LatencyReport {
+            min_ms: round_ms(latencies.min()),
+            mean_ms: round_ms(latencies.mean()),
+            max_ms: round_ms(latencies.max()),
+            stdev_ms: round_ms(latencies.stdev()),
+            p50_ms: round_ms(latencies.percentile(50.0)),
+            p90_ms: round_ms(latencies.percentile(90.0)),
+            p95_ms: round_ms(latencies.percentile(95.0)),
+            p99_ms: round_ms(latencies.percentile(99.0)),
+            p999_ms: round_ms(latencies.percentile(99.9)),
+        }
+    }
+}
+
+impl LatencyReport {
+    pub fn fields(&self) -> [(&'static str, f64); 9] {
+        [
+            ("min_ms", self.min_ms),
+            ("mean_ms", self.mean_ms),
+            ("max_ms", self.max_ms),
+            ("stdev_ms", self.stdev_ms),
+            ("p50_ms", self.p50_ms),
+            ("p90_ms", self.p90_ms),
+            ("p95_ms", self.p95_ms),
+            ("p99_ms", self.p99_ms),
+            ("p999_ms", self.p999_ms),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EndpointReport {
+    pub name: String,
+    pub count: u64,
+    pub errors: u64,
+    pub rps: f64,
+    pub latency: LatencyReport,
+}
+
+impl EndpointReport {
+    pub fn from_stats(stats: &RunStats, elapsed: Duration) -> Vec<EndpointReport> {
+        stats
+            .endpoints
+            .iter()
+            .map(|(name, endpoint)| EndpointReport {
+                name: name.to_string(),
+                count: endpoint.total,
+                errors: endpoint.failed,
+                rps: to_f64(throughput(endpoint.total, elapsed)),
+                latency: LatencyReport::from(&endpoint.latencies),
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StageReport {
+    pub duration_ms: f64,
+    pub target_rps: f64,
+    pub count: u64,
+    pub errors: u64,
+    pub queued: u64,
+    pub rps: f64,
+    pub latency: LatencyReport,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_time: Option<LatencyReport>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PageLoadReport {
+    pub count: u64,
+    /// Pages with at least one failed request
+    pub failed: u64,
+    pub latency: LatencyReport,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DistributionBucket {
+    /// Upper bound of the bucket
+    pub le_ms: f64,
+    pub count: u64,
+}
+
+/// Requests sent within one timeline interval
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TimelinePoint {
+    /// Seconds since the first request of the run
+    pub offset_s: u64,
+    pub interval_s: u64,
+    pub count: u64,
+    pub errors: u64,
+    pub rps: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl TimelinePoint {
+    fn from_bucket(offset_s: u64, interval_s: u64, bucket: &mut TimelineBucket) -> TimelinePoint {
+        bucket.latencies_us.sort_unstable();
+        let latencies = &bucket.latencies_us;
+        let percentile = |percentile: f64| -> f64 {
+            let rank = (latencies.len() as f64 * percentile / 100.0).ceil() as usize;
+            let micros = latencies[rank.clamp(1, latencies.len()) - 1];
+            round_ms(Duration::from_micros(u64::from(micros)))
+        };
+
+        TimelinePoint {
+            offset_s,
+            interval_s,
+            count: bucket.total,
+            errors: bucket.failed,
+            rps: to_f64(throughput(bucket.total, Duration::from_secs(interval_s))),
+            p50_ms: percentile(50.0),
+            p95_ms: percentile(95.0),
+            p99_ms: percentile(99.0),
+            max_ms: percentile(100.0),
+        }
+    }
+
+    /// Combines adjacent seconds into at most `MAX_TIMELINE_POINTS` intervals
+    fn from_stats(stats: &RunStats) -> Vec<TimelinePoint> {
+        let Some(timeline) = &stats.timeline else {
+            return Vec::new();
+        };
+        let (Some(first), Some(last)) = (timeline.keys().next(), timeline.keys().next_back())
+        else {
+            return Vec::new();
+        };
+        let interval_s = (last - first + 1).div_ceil(MAX_TIMELINE_POINTS);
+
+        let mut groups: BTreeMap<u64, TimelineBucket> = BTreeMap::new();
+        for (second, bucket) in timeline.iter() {
+            let offset_s = (second - first) / interval_s * interval_s;
+            groups.entry(offset_s).or_default().merge(bucket);
+        }
+
+        groups
+            .iter_mut()
+            .map(|(offset_s, bucket)| TimelinePoint::from_bucket(*offset_s, interval_s, bucket))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub group: String,
+    pub count: u64,
+    pub sample: String,
+}
+
+/// Full run summary, written as JSON or CSV
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunReport {
+    pub command: String,
+    pub started_at: String,
+    pub finished_at: String,
+    /// Config used for the run, without the token
+    pub config: serde_json::Value,
+    pub total: u64,
+    pub succeed: u64,
+    pub failed: u64,
+    pub retries: u64,
+    #[serde(default)]
+    pub queued: u64,
+    pub success_rate: Option<f64>,
+    pub rps: f64,
+    pub elapsed_ms: f64,
+    pub run_duration_ms: f64,
+    /// Service time of every request
+    pub latency: LatencyReport,
+    /// Latency from the intended send time, present for rate limited runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_time: Option<LatencyReport>,
+    /// Time to load whole issue pages, present for page crawls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_load: Option<PageLoadReport>,
+    pub statuses: BTreeMap<u16, u64>,
+    pub endpoints: Vec<EndpointReport>,
+    pub errors: Vec<ErrorReport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageReport>,
+    #[serde(default)]
+    pub distribution: Vec<DistributionBucket>,
+    #[serde(default)]
+    pub timeline: Vec<TimelinePoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub samples: Option<Vec<Sample>>,
+}
+
+impl RunReport {
+    pub fn build(
+        command: &str,
+        config: &Config,
+        started_at: SystemTime,
+        stats: &RunStats,
+        elapsed: Duration,
+        run_duration: Duration,
+    ) -> Result<RunReport> {
+        let endpoints = EndpointReport::from_stats(stats, elapsed);
+
+        let stages = stats
+            .stages
+            .iter()
+            .map(|stage| StageReport {
+                duration_ms: round_ms(stage.duration),
+                target_rps: stage.target_rps,
+                count: stage.total,
+                errors: stage.failed,
+                queued: stage.queued,
+                rps: to_f64(throughput(stage.total, stage.duration)),
+                latency: LatencyReport::from(&stage.latencies),
+                response_time: response_time_report(&stage.response_times),
+            })
+            .collect();
+
+        let mut errors: Vec<ErrorReport> = stats
+            .errors
+            .iter()
+            .map(|(group, errors)| ErrorReport {
+                group: group.clone(),
+                count: errors.count,
+                sample: errors.sample.clone(),
+            })
+            .collect();
+        errors.sort_by_key(|errors| Reverse(errors.count));
+
+        Ok(RunReport {
+            command: command.to_string(),
+            started_at: humantime::format_rfc3339_millis(started_at).to_string(),
+            finished_at: humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+            config: serde_json::to_value(config)?,
+            total: stats.total,
+            succeed: stats.succeed(),
+            failed: stats.failed,
+            retries: stats.retries,
+            queued: stats.queued,
+            success_rate: stats.success_rate().map(to_f64),
+            rps: to_f64(stats.rps(elapsed)),
+            elapsed_ms: round_ms(elapsed),
+            run_duration_ms: round_ms(run_duration),
+            latency: LatencyReport::from(&stats.latencies),
+            response_time: response_time_report(&stats.response_times),
+            page_load: page_load_report(stats),
+            statuses: stats.statuses.clone(),
+            endpoints,
+            errors,
+            stages,
+            distribution: stats
+                .latencies
+                .distribution(DISTRIBUTION_BUCKETS)
+                .into_iter()
+                .map(|(le, count)| DistributionBucket {
+                    le_ms: round_ms(le),
+                    count,
+                })
+                .collect(),
+            timeline: TimelinePoint::from_stats(stats),
+            samples: stats.samples.clone(),
+        })
+    }
+
+    pub fn load(filename: &Path) -> Result<RunReport> {
+        let contents = fs::read_to_string(filename)?;
+        Ok(serde_json::from_str(contents.as_str())?)
+    }
+
+    /// Writes the report to the file or to stdout when no file is given
+    pub fn write(&self, format: ReportFormat, filename: Option<&Path>) -> Result<()> {
+        let contents = match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Csv => self.to_csv(),
+        };
+
+        match filename {
+            Some(filename) => {
+                fs::write(filename, contents)?;
+                println!("Report written to {}", filename.display());
+            }
+            None => println!("{}", contents),
+        }
+
+        Ok(())
+    }
+
+    /// Long format CSV, one `section,name,metric,value` row per value
+    fn to_csv(&self) -> String {
+        let mut rows: Vec<[String; 4]> = Vec::new();
+        let mut push = |section: &str, name: &str, metric: &str, value: String| {
+            rows.push([
+                section.to_string(),
+                name.to_string(),
+                metric.to_string(),
+                value,
+            ]);
+        };
+
+        push("run", "", "command", self.command.clone());
+        push("run", "", "started_at", self.started_at.clone());
+        push("run", "", "finished_at", self.finished_at.clone());
+
+        if let Some(config) = self.config.as_object() {
+            for (key, value) in config.iter() {
+                let value = match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                push("config", "", key, value);
+            }
+        }
+
+        push("summary", "", "total", self.total.to_string());
+        push("summary", "", "succeed", self.succeed.to_string());
+        push("summary", "", "failed", self.failed.to_string());
+        push("summary", "", "retries", self.retries.to_string());
+        push("summary", "", "queued", self.queued.to_string());
+        if let Some(rate) = self.success_rate {
+            push("summary", "", "success_rate", rate.to_string());
+        }
+        push("summary", "", "rps", self.rps.to_string());
+        push("summary", "", "elapsed_ms", self.elapsed_ms.to_string());
+        push(
+            "summary",
+            "",
+            "run_duration_ms",
+            self.run_duration_ms.to_string(),
+        );
+        for (metric, value) in self.latency.fields() {
+            push("summary", "", metric, format!("{:.3}", value));
+        }
+
+        if let Some(response_time) = &self.response_time {
+            for (metric, value) in response_time.fields() {
+                push("response_time", "", metric, format!("{:.3}", value));
+            }
+        }
+
+        if let Some(page_load) = &self.page_load {
+            push("page_load", "", "count", page_load.count.to_string());
+            push("page_load", "", "failed", page_load.failed.to_string());
+            for (metric, value) in page_load.latency.fields() {
+                push("page_load", "", metric, format!("{:.3}", value));
+            }
+        }
+
+        for (status, count) in self.statuses.iter() {
+            push(
+                "status",
+                status.to_string().as_str(),
+                "count",
+                count.to_string(),
+            );
+        }
+
+        for endpoint in self.endpoints.iter() {
+            let name = endpoint.name.as_str();
+            push("endpoint", name, "count", endpoint.count.to_string());
+            push("endpoint", name, "errors", endpoint.errors.to_string());
+            push("endpoint", name, "rps", endpoint.rps.to_string());
+            for (metric, value) in endpoint.latency.fields() {
+                push("endpoint", name, metric, format!("{:.3}", value));
+            }
+        }
+
+        for (index, stage) in self.stages.iter().enumerate() {
+            let name = (index + 1).to_string();
+            push("stage", &name, "duration_ms", stage.duration_ms.to_string());
+            push("stage", &name, "target_rps", stage.target_rps.to_string());
+            push("stage", &name, "count", stage.count.to_string());
+            push("stage", &name, "errors", stage.errors.to_string());
+            push("stage", &name, "queued", stage.queued.to_string());
+            push("stage", &name, "rps", stage.rps.to_string());
+            for (metric, value) in stage.latency.fields() {
+                push("stage", &name, metric, format!("{:.3}", value));
+            }
+            if let Some(response_time) = &stage.response_time {
+                for (metric, value) in response_time.fields() {
+                    let metric = format!("response_{}", metric);
+                    push("stage", &name, &metric, format!("{:.3}", value));
+                }
+            }
+        }
+
+        for bucket in self.distribution.iter() {
+            let name = format!("{:.3}", bucket.le_ms);
+            push("distribution", &name, "count", bucket.count.to_string());
+        }
+
+        for point in self.timeline.iter() {
+            let name = point.offset_s.to_string();
+            push(
+                "timeline",
+                &name,
+                "interval_s",
+                point.interval_s.to_string(),
+            );
+            push("timeline", &name, "count", point.count.to_string());
+            push("timeline", &name, "errors", point.errors.to_string());
+            push("timeline", &name, "rps", point.rps.to_string());
+            push("timeline", &name, "p50_ms", format!("{:.3}", point.p50_ms));
+            push("timeline", &name, "p95_ms", format!("{:.3}", point.p95_ms));
+            push("timeline", &name, "p99_ms", format!("{:.3}", point.p99_ms));
+            push("timeline", &name, "max_ms", format!("{:.3}", point.max_ms));
+        }
+
+        for errors in self.errors.iter() {
+            let group = errors.group.as_str();
+            push("error", group, "count", errors.count.to_string());
+            push("error", group, "sample", errors.sample.clone());
+        }
+
+        if let Some(samples) = &self.samples {
+            for (index, sample) in samples.iter().enumerate() {
+                let name = index.to_string();
+                push("sample", &name, "endpoint", sample.endpoint.clone());
+                push(
+                    "sample",
+                    &name,
+                    "timestamp_ms",
+                    sample.timestamp_ms.to_string(),
+                );
+                push(
+                    "sample",
+                    &name,
+                    "duration_ms",
+                    format!("{:.3}", sample.duration_ms),
+                );
+                let status = sample.status.map(|s| s.to_string()).unwrap_or_default();
+                push("sample", &name, "status", status);
+                push("sample", &name, "success", sample.success.to_string());
+                push("sample", &name, "retries", sample.retries.to_string());
+                push(
+                    "sample",
+                    &name,
+                    "error",
+                    sample.error.clone().unwrap_or_default(),
+                );
+            }
+        }
+
+        let mut csv = String::from("section,name,metric,value\n");
+        for row in rows {
+            let fields: Vec<String> = row.iter().map(|field| escape_csv(field)).collect();
+            csv.push_str(fields.join(",").as_str());
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Values are already rounded to 2 decimals, round again to drop float noise
+pub fn to_f64(value: bigdecimal::BigDecimal) -> f64 {
+    let value = value.to_f64().unwrap_or(0.0);
+    (value * 100.0).round() / 100.0
+}
+
+fn page_load_report(stats: &RunStats) -> Option<PageLoadReport> {
+    if stats.page_loads.is_empty() {
+        return None;
+    }
+    Some(PageLoadReport {
+        count: stats.page_loads.len(),
+        failed: stats.failed_pages,
+        latency: LatencyReport::from(&stats.page_loads),
+    })
+}
+
+fn response_time_report(response_times: &LatencyHistogram) -> Option<LatencyReport> {
+    if response_times.is_empty() {
+        return None;
+    }
+    Some(LatencyReport::from(response_times))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_csv_leaves_plain_fields() {
+        assert_eq!(escape_csv("issues.get"), "issues.get");
+        assert_eq!(escape_csv(""), "");
+        assert_eq!(escape_csv("p99 ms"), "p99 ms");
+    }
+
+    #[test]
+    fn escape_csv_quotes_special_characters() {
+        assert_eq!(escape_csv("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(escape_csv("carriage\rreturn"), "\"carriage\rreturn\"");
+    }
+
+    #[test]
+    fn escape_csv_doubles_quotes() {
+        assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_csv("\""), "\"\"\"\"");
+    }
+}
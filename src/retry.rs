@@ -0,0 +1,83 @@
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Retry policy for transient failures, configured via the `[retry]` section.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one, 1 disables retries
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// Randomize delays between zero and the computed backoff (full jitter)
+    pub jitter: bool,
+    /// Wait as long as the server asks when a Retry-After header is present, up to `max_delay_ms`
+    pub honour_retry_after: bool,
+    pub statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: 200,
+            max_delay_ms: 10_000,
+            jitter: true,
+            honour_retry_after: true,
+            statuses: vec![429, 500, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.max_attempts == 0 {
+            return Err("Retry max attempts must be at least 1");
+        }
+        if self.base_delay_ms > self.max_delay_ms {
+            return Err("Retry base delay must not exceed max delay");
+        }
+        Ok(())
+    }
+
+    pub fn is_retryable_status(&self, status: StatusCode) -> bool {
+        self.statuses.contains(&status.as_u16())
+    }
+
+    pub fn is_retryable_error(&self, err: &reqwest::Error) -> bool {
+        err.is_connect() || err.is_timeout() || err.is_request()
+    }
+
+    /// Delay before the next attempt, `attempt` being the 1-based attempt that just failed.
+    pub fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if self.honour_retry_after {
+            if let Some(delay) = headers.and_then(parse_retry_after) {
+                return delay.min(Duration::from_millis(self.max_delay_ms));
+            }
+        }
+
+        let exp = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay_ms
+            .saturating_mul(1u64 << exp)
+            .min(self.max_delay_ms);
+
+        let millis = if self.jitter && backoff > 0 {
+            rand::thread_rng().gen_range(0..=backoff)
+        } else {
+            backoff
+        };
+
+        Duration::from_millis(millis)
+    }
+}
+
+/// Only the delay-seconds form is supported, HTTP dates are ignored
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    let seconds: u64 = value.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
@@ -1,42 +1,99 @@
-use bigdecimal::BigDecimal;
-use std::time::Instant;
-
-use fake::faker::company::en::CatchPhase;
-use fake::Fake;
-use rand::Rng;
-
-use crate::config::Config;
-use crate::crawler::{
-    create_issue, fetch_epics, fetch_initiatives, fetch_issue, fetch_issues, fetch_labels,
-    fetch_me, fetch_members, fetch_project, fetch_projects, fetch_statuses,
-};
-use crate::error::Result;
-use crate::model::{CreateIssueBody, Issue, IssueStatus, PaginationResult, Project, ProjectSlim};
+use anyhow::anyhow;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::{Config, CrawlInclude, ScenarioArgs, SeedArgs};
+use crate::crawler::{ApiClient, IssuePage, ResponseData, ISSUE_PAGE_REQUESTS};
+use crate::error::{Result, ThresholdError};
+use crate::generator::{IssueGenerator, NewComment};
+use crate::metrics::MetricsServer;
+use crate::model::{CreateIssueBody, Issue, PaginationResult, Project, ProjectSlim};
+use crate::pool::WorkerPool;
+use crate::progress::ProgressDisplay;
+use crate::report::RunReport;
+use crate::scenario::{self, Scenario, UserResult};
+use crate::schedule::{ArrivalSchedule, ScheduledResponse};
+use crate::stats::RunStats;
+use crate::thresholds::print_checks;
+use crate::timeseries::TimeSeriesWriter;
 
 pub async fn run(config: Config) -> Result<()> {
+    let started_at = SystemTime::now();
     let timer = Instant::now();
-    let current_user = fetch_me(&config).await?;
+    let client = ApiClient::new(&config)?;
+    let _metrics = MetricsServer::start(&config, &client).await?;
+    let create_timer = Instant::now();
+    let mut stats = RunStats::new(&config).with_stages(&config.stages);
+    let current_user = required(&client, &mut stats, client.fetch_me().await?)?;
     println!("Logged in as: {}", current_user.username);
 
-    let project = fetch_project(&config, config.project_id.as_str()).await?;
+    let project_id = config.project_id.as_str();
+    let project = required(&client, &mut stats, client.fetch_project(project_id).await?)?;
     println!("{}: {}", project.key, project.name);
 
+    let mut generator = build_generator(&client, &config, project, &mut stats).await?;
+    generator.issue_mix = config
+        .issue_mix
+        .iter()
+        .map(|(issue_type, weight)| (issue_type.clone(), *weight))
+        .collect();
+    generator.initiatives = required(
+        &client,
+        &mut stats,
+        client.fetch_initiatives(project_id).await?,
+    )?;
+    generator.epics = required(&client, &mut stats, client.fetch_epics(project_id).await?)?;
+
+    let progress = client.progress();
+    match load_duration(&config) {
+        Some(duration) => progress.set_deadline(duration),
+        None => progress.add_total(u64::from(config.issue_count)),
+    }
+    let display = progress.display();
+    let timeseries = TimeSeriesWriter::start(&config, &client)?;
+
+    if let Some(mut schedule) = create_schedule(&config) {
+        create_on_schedule(&client, &config, &generator, &mut schedule, &mut stats).await;
+    } else if let Some(duration) = config.duration {
+        create_for_duration(&client, &config, &generator, duration, &mut stats).await;
+    } else {
+        create_count(&client, &config, &generator, &mut stats).await;
+    }
+    display.stop();
+    if let Some(timeseries) = timeseries {
+        timeseries.finish().await?;
+    }
+
+    finish_run(
+        "create",
+        &config,
+        started_at,
+        &stats,
+        create_timer.elapsed(),
+        timer.elapsed(),
+    )
+}
+
+/// Generator from the project's labels, statuses and members, without parents to link to
+async fn build_generator(
+    client: &ApiClient,
+    config: &Config,
+    project: Project,
+    stats: &mut RunStats,
+) -> Result<IssueGenerator> {
+    let project_id = config.project_id.as_str();
+
     // Collect statuses and labels
-    let labels = fetch_labels(&config, config.project_id.as_str()).await?;
-    let mut statuses = fetch_statuses(&config, config.project_id.as_str()).await?;
+    let labels = required(client, stats, client.fetch_labels(project_id).await?)?;
+    let mut statuses = required(client, stats, client.fetch_statuses(project_id).await?)?;
 
     // Remove last status, should not create issues as done
-    if statuses.len() > 0 {
+    if !statuses.is_empty() {
         statuses.pop();
     }
 
-    let initiatives = fetch_initiatives(&config, config.project_id.as_str()).await?;
-    let epics = fetch_epics(&config, config.project_id.as_str()).await?;
-    let members = fetch_members(&config, config.project_id.as_str()).await?;
-    let hours = vec![
-        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20,
-    ];
-    let points = vec![1, 2, 3, 5, 8, 13, 21];
+    let members = required(client, stats, client.fetch_members(project_id).await?)?;
 
     let project_preferences = project.preferences.unwrap();
 
@@ -46,395 +103,650 @@ pub async fn run(config: Config) -> Result<()> {
         None => project_preferences.issue_type.clone(),
     };
 
-    let create_timer = Instant::now();
+    let seed = config.seed.expect("Seed is set when loading the config");
+    println!("Seed: {}", seed);
+
+    let mut generator = IssueGenerator::new(
+        issue_type,
+        project_preferences.estimate_type.clone(),
+        labels,
+        statuses,
+        members,
+        seed,
+    );
+    generator.description_sections = config.descriptions.sections.clone();
+    Ok(generator)
+}
 
-    let mut handles = vec![];
+/// Creates exactly `issue_count` issues as fast as the worker pool allows
+async fn create_count(
+    client: &ApiClient,
+    config: &Config,
+    generator: &IssueGenerator,
+    stats: &mut RunStats,
+) {
+    let mut pool: WorkerPool<IssueRequests> = WorkerPool::new(config.concurrency);
 
     for _ in 0..config.issue_count {
-        let member = get_random_item(&members, 30);
-        let label = get_random_item(&labels, 30);
+        let (task, comments) = next_issue(client, config, generator);
+        let task = ScheduledResponse::unscheduled(task);
+        pool.spawn(create_with_comments(task, client, config, comments))
+            .await;
+    }
 
-        let mut initiative: Option<&Issue> = None;
-        let mut epic: Option<&Issue> = None;
-        let mut status: Option<&IssueStatus> = None;
+    // Gather stats
+    while let Some(requests) = pool.join_next().await {
+        requests.record(stats);
+    }
+}
 
-        // Initiatives and epics do not have these properties
-        match issue_type.as_str() {
-            "initiative" => {
-                // Do nothing...
-            }
-            "epic" => {
-                initiative = get_random_item(&initiatives, 20);
-            }
-            _ => {
-                epic = get_random_item(&epics, 20);
-                status = get_random_item(&statuses, 100);
-            }
-        };
+/// Closed model, every worker creates issues back to back until the duration is over
+async fn create_for_duration(
+    client: &ApiClient,
+    config: &Config,
+    generator: &IssueGenerator,
+    duration: Duration,
+    stats: &mut RunStats,
+) {
+    let mut pool: WorkerPool<IssueRequests> = WorkerPool::new(config.concurrency);
+    let deadline = Instant::now() + duration;
+
+    while Instant::now() < deadline {
+        let (task, comments) = next_issue(client, config, generator);
+        let task = ScheduledResponse::unscheduled(task);
+        pool.spawn(create_with_comments(task, client, config, comments))
+            .await;
+
+        // Collect whatever finished so far to keep memory flat on long runs
+        pool.join_until(Instant::now().into(), |requests| requests.record(stats))
+            .await;
+    }
 
-        let default_labels: Vec<String> = vec![];
+    while let Some(requests) = pool.join_next().await {
+        requests.record(stats);
+    }
+}
 
-        let title: String = CatchPhase().fake();
-        let description = format!(
-            "{}, {}, {}, {}",
-            CatchPhase().fake::<String>(),
-            CatchPhase().fake::<String>(),
-            CatchPhase().fake::<String>(),
-            CatchPhase().fake::<String>()
-        );
+pub async fn seed(config: Config, args: &SeedArgs) -> Result<()> {
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let [initiative_count, epic_count, story_count, subtask_count] = args
+        .level_counts()
+        .ok_or_else(|| anyhow!("Seed tree is too large, reduce the issues per level"))?;
+    let client = ApiClient::new(&config)?;
+    let _metrics = MetricsServer::start(&config, &client).await?;
+    let seed_timer = Instant::now();
+    let mut stats = RunStats::new(&config);
+    let current_user = required(&client, &mut stats, client.fetch_me().await?)?;
+    println!("Logged in as: {}", current_user.username);
 
-        let mut payload = CreateIssueBody {
-            r#type: issue_type.clone(),
-            initiative_id: None,
-            epic_id: None,
-            parent_id: None,
-            assignee_id: None,
-            title,
-            description: Some(description),
-            estimate_type: Some(project_preferences.estimate_type.clone()),
-            estimate: Some(10),
-            status: None,
-            labels: default_labels,
-        };
+    let project_id = config.project_id.as_str();
+    let project = required(&client, &mut stats, client.fetch_project(project_id).await?)?;
+    println!("{}: {}", project.key, project.name);
 
-        if project_preferences.estimate_type == String::from("points") {
-            let estimate = get_random_item(&points, 100);
-            payload.estimate = Some(*estimate.unwrap());
-        } else {
-            let estimate = get_random_item(&hours, 100);
-            payload.estimate = Some(*estimate.unwrap());
-        }
+    let generator = build_generator(&client, &config, project, &mut stats).await?;
+
+    let progress = client.progress();
+    progress.add_total(initiative_count + epic_count + story_count + subtask_count);
+    let display = progress.display();
+    let timeseries = TimeSeriesWriter::start(&config, &client)?;
+
+    // Parents are created first so their ids can be wired into the children
+    let initiatives = create_level(
+        &client,
+        &config,
+        &generator,
+        (0..args.initiatives).map(|_| generator.generate_for("initiative")),
+        &mut stats,
+    )
+    .await;
+    let epics = create_level(
+        &client,
+        &config,
+        &generator,
+        children(&initiatives, args.epics, |initiative| {
+            let mut payload = generator.generate_for("epic");
+            payload.initiative_id = Some(initiative.id.clone());
+            payload
+        }),
+        &mut stats,
+    )
+    .await;
+    let stories = create_level(
+        &client,
+        &config,
+        &generator,
+        children(&epics, args.stories, |epic| {
+            let mut payload = generator.generate_for("user_story");
+            payload.epic_id = Some(epic.id.clone());
+            payload
+        }),
+        &mut stats,
+    )
+    .await;
+    let subtasks = create_level(
+        &client,
+        &config,
+        &generator,
+        children(&stories, args.subtasks, |story| {
+            let mut payload = generator.generate_for("task");
+            payload.parent_id = Some(story.id.clone());
+            payload
+        }),
+        &mut stats,
+    )
+    .await;
+    display.stop();
+    if let Some(timeseries) = timeseries {
+        timeseries.finish().await?;
+    }
 
-        if let Some(initiative_value) = initiative {
-            payload.initiative_id = Some(String::from(initiative_value.id.as_str()));
-        }
+    // Children of failed parents are never attempted
+    println!(
+        "Created {} of {} initiatives, {} of {} epics, {} of {} stories, {} of {} subtasks",
+        initiatives.len(),
+        initiative_count,
+        epics.len(),
+        epic_count,
+        stories.len(),
+        story_count,
+        subtasks.len(),
+        subtask_count
+    );
+
+    finish_run(
+        "seed",
+        &config,
+        started_at,
+        &stats,
+        seed_timer.elapsed(),
+        timer.elapsed(),
+    )
+}
 
-        if let Some(epic_value) = epic {
-            payload.epic_id = Some(String::from(epic_value.id.as_str()));
-        }
-        if let Some(member_value) = member {
-            if let Some(user_value) = &member_value.user {
-                payload.assignee_id = Some(String::from(user_value.id.as_str()));
-            }
-        }
-        if let Some(status_value) = status {
-            payload.status = Some(String::from(status_value.id.as_str()));
-        }
-        if let Some(label_value) = label {
-            payload.labels = vec![String::from(label_value.id.as_str())];
+/// `count` payloads for every parent
+fn children<'a, F>(
+    parents: &'a [Issue],
+    count: u32,
+    payload: F,
+) -> impl Iterator<Item = CreateIssueBody> + 'a
+where
+    F: Fn(&Issue) -> CreateIssueBody + 'a,
+{
+    parents
+        .iter()
+        .flat_map(move |parent| (0..count).map(move |_| parent))
+        .map(payload)
+}
+
+/// Creates one level of the tree and returns the issues that were created
+async fn create_level(
+    client: &ApiClient,
+    config: &Config,
+    generator: &IssueGenerator,
+    payloads: impl Iterator<Item = CreateIssueBody>,
+    stats: &mut RunStats,
+) -> Vec<Issue> {
+    let mut pool: WorkerPool<(usize, IssueRequests)> = WorkerPool::new(config.concurrency);
+    let mut created: Vec<(usize, Issue)> = Vec::new();
+
+    for (index, payload) in payloads.enumerate() {
+        let comments = generator.generate_comments(&config.comments);
+        let task = ScheduledResponse::unscheduled(create_task(client, config, payload));
+        let task = create_with_comments(task, client, config, comments);
+        pool.spawn(async move { (index, task.await) }).await;
+    }
+
+    while let Some((index, requests)) = pool.join_next().await {
+        requests.record(stats);
+        if let Some(issue) = requests.issue.res.data {
+            created.push((index, issue));
         }
+    }
 
-        let config_copy = config.clone();
-        let handle = tokio::spawn(async move {
-            create_issue(&config_copy, config_copy.project_id.as_str(), &payload)
-                .await
-                .unwrap()
-        });
+    // Keep the order the payloads were generated in so seeded runs stay reproducible
+    created.sort_by_key(|(index, _)| *index);
+    created.into_iter().map(|(_, issue)| issue).collect()
+}
 
-        handles.push(handle);
+/// How long the run lasts when bound by time instead of a request count
+fn load_duration(config: &Config) -> Option<Duration> {
+    if !config.stages.is_empty() {
+        return Some(config.stages.iter().map(|stage| stage.duration).sum());
     }
+    config.duration
+}
 
-    // Gather stats
-    let total_reqs: u32 = handles.len().try_into().unwrap();
-    let mut failed: u32 = 0;
-    let mut min_duration: u128 = 0;
-    let mut max_duration: u128 = 0;
-    let mut sum: u128 = 0;
-
-    for handle in handles {
-        let res = handle.await.unwrap();
-        if let None = res.data {
-            failed += 1;
-        }
+/// Arrival schedule of the create command, from the stages or the target rps
+fn create_schedule(config: &Config) -> Option<ArrivalSchedule> {
+    if !config.stages.is_empty() {
+        return Some(ArrivalSchedule::from_stages(&config.stages));
+    }
 
-        sum += res.duration;
+    // Without a duration the rate applies until `issue_count` issues are sent
+    let limit = match config.duration {
+        Some(_) => None,
+        None => Some(u64::from(config.issue_count)),
+    };
+    config
+        .rps
+        .map(|rps| ArrivalSchedule::constant(rps, config.duration, limit))
+}
 
-        if min_duration == 0 {
-            min_duration = res.duration;
-        } else if res.duration < min_duration {
-            min_duration = res.duration;
+/// Open model, issues are sent on schedule whether or not earlier requests have completed.
+/// Arrivals that find every worker busy wait in a queue and are counted as queued.
+async fn create_on_schedule(
+    client: &ApiClient,
+    config: &Config,
+    generator: &IssueGenerator,
+    schedule: &mut ArrivalSchedule,
+    stats: &mut RunStats,
+) {
+    let mut pool: WorkerPool<IssueRequests> = WorkerPool::new(config.concurrency);
+
+    while let Some(arrival) = schedule.next_arrival() {
+        pool.join_until(arrival.at, |requests| requests.record(stats))
+            .await;
+
+        // Every arrival is sent, so payloads follow the schedule whatever the response times
+        let (task, comments) = next_issue(client, config, generator);
+        let task = create_with_comments(arrival.run(task), client, config, comments);
+        if pool.queue(task) {
+            stats.record_queued(arrival.stage);
         }
+    }
 
-        if res.duration > max_duration {
-            max_duration = res.duration;
-        }
+    while let Some(requests) = pool.join_next().await {
+        requests.record(stats);
     }
+}
 
-    let succeed = total_reqs - failed;
-    let big_success_ratio =
-        (BigDecimal::from(succeed) / BigDecimal::from(total_reqs)) * BigDecimal::from(100);
-    let success_ratio = big_success_ratio.round(2);
-    let big_sum = BigDecimal::from(sum);
-    let big_total_reqs = BigDecimal::from(total_reqs);
-    let big_avg = big_sum / big_total_reqs.clone();
-    let avg = big_avg.round(2);
-
-    let total_time = timer.elapsed().as_millis();
-    let total_create_time = create_timer.elapsed().as_millis();
-    let big_create_total_time = BigDecimal::from(total_create_time);
-    let big_rps: BigDecimal = big_total_reqs / (big_create_total_time / 1000.0);
-    let rps = big_rps.round(2);
-
-    // Print stats
-    println!("");
-    println!("Total requests: {}", total_reqs);
-    println!("Succeed: {}", succeed);
-    println!("Failed: {}", failed);
-    println!("Success rate: {}%", success_ratio);
-    println!("Min: {} ms", min_duration);
-    println!("Avg: {} ms", avg);
-    println!("Max: {} ms", max_duration);
-    println!("Requests per second: {}", rps);
-    println!("Run duration: {} ms", total_time);
+fn create_task(
+    client: &ApiClient,
+    config: &Config,
+    payload: CreateIssueBody,
+) -> impl Future<Output = ResponseData<Issue>> {
+    let client_copy = client.clone();
+    let project_id = config.project_id.clone();
+    async move {
+        client_copy
+            .create_issue(project_id.as_str(), &payload)
+            .await
+            .unwrap()
+    }
+}
 
-    Ok(())
+/// Next issue payload along with its comments, generated together so seeded runs stay reproducible
+fn next_issue(
+    client: &ApiClient,
+    config: &Config,
+    generator: &IssueGenerator,
+) -> (impl Future<Output = ResponseData<Issue>>, Vec<NewComment>) {
+    let task = create_task(client, config, generator.generate());
+    (task, generator.generate_comments(&config.comments))
 }
 
-fn get_item_chance(chance: u32) -> bool {
-    if chance > 100 {
-        panic!("Chance must be between 0 to 100")
+/// Creates the issue then posts its comments one after the other
+fn create_with_comments(
+    task: impl Future<Output = ScheduledResponse<Issue>>,
+    client: &ApiClient,
+    config: &Config,
+    comments: Vec<NewComment>,
+) -> impl Future<Output = IssueRequests> {
+    let client = client.clone();
+    let project_id = config.project_id.clone();
+    client.progress().add_total(comments.len() as u64);
+    async move {
+        let issue = task.await;
+        let mut related: Vec<ResponseData<()>> = Vec::new();
+        if let Some(created) = &issue.res.data {
+            for comment in comments {
+                let res = client
+                    .create_comment(
+                        project_id.as_str(),
+                        created.id.as_str(),
+                        &comment.body,
+                        comment.token.as_deref(),
+                    )
+                    .await
+                    .unwrap();
+                related.push(res.without_data());
+            }
+        }
+        IssueRequests { issue, related }
     }
+}
 
-    let value = rand::thread_rng().gen_range(0..=100);
-    value <= chance
+/// Records a request the command cannot continue without and returns its body.
+/// Commands start their measured window before these so rates include them.
+fn required<T>(client: &ApiClient, stats: &mut RunStats, res: ResponseData<T>) -> Result<T> {
+    client.progress().add_total(1);
+    stats.record(&res);
+    let endpoint = res.endpoint;
+    res.data
+        .ok_or_else(|| anyhow!("Request {} failed", endpoint))
 }
 
-fn get_random_item<T>(items: &Vec<T>, chance: u32) -> Option<&T> {
-    let length = items.len();
-    let return_item = get_item_chance(chance);
+/// Prints the summary, writes the report when requested and checks the thresholds
+fn finish_run(
+    command: &str,
+    config: &Config,
+    started_at: SystemTime,
+    stats: &RunStats,
+    elapsed: Duration,
+    run_duration: Duration,
+) -> Result<()> {
+    stats.print_summary(elapsed, run_duration);
+
+    if config.report.is_some() || config.html_report.is_some() {
+        let report = RunReport::build(command, config, started_at, stats, elapsed, run_duration)?;
+        if let Some(format) = config.report {
+            report.write(format, config.report_file.as_deref())?;
+        }
+        if let Some(html_report) = &config.html_report {
+            report.write_html(html_report)?;
+        }
+    }
 
-    if length > 0 && return_item {
-        let max_length = length - 1;
-        let key = rand::thread_rng().gen_range(0..=max_length);
-        return items.get(key);
+    let checks = config.thresholds.evaluate(stats, elapsed);
+    if !checks.is_empty() {
+        print_checks(&checks);
+        let failed = checks.iter().filter(|check| !check.passed).count();
+        if failed > 0 {
+            return Err(ThresholdError { failed }.into());
+        }
     }
-    None
+
+    Ok(())
 }
 
 pub async fn crawl_project_issues(config: Config) -> Result<()> {
+    let started_at = SystemTime::now();
     let timer = Instant::now();
-    let current_user = fetch_me(&config).await?;
+    let client = ApiClient::new(&config)?;
+    let _metrics = MetricsServer::start(&config, &client).await?;
+    let crawl_timer = Instant::now();
+    let mut stats = RunStats::new(&config);
+    let current_user = required(&client, &mut stats, client.fetch_me().await?)?;
     println!("Logged in as: {}", current_user.username);
 
-    let project_id = config.project_id.clone();
-    let project = fetch_project(&config, project_id.as_str()).await?;
+    let project_id = config.project_id.as_str();
+    let project = required(&client, &mut stats, client.fetch_project(project_id).await?)?;
     println!("{}: {}", project.key, project.name);
 
+    let display = crawl_progress(&client, &config);
+    let timeseries = TimeSeriesWriter::start(&config, &client)?;
+    let mut schedule = crawl_schedule(&config);
+    let crawl_stats = crawl_issues(&client, &config, project_id, schedule.as_mut()).await?;
+    stats.merge(&crawl_stats);
+    display.stop();
+    if let Some(timeseries) = timeseries {
+        timeseries.finish().await?;
+    }
+
+    finish_run(
+        "crawl-issues",
+        &config,
+        started_at,
+        &stats,
+        crawl_timer.elapsed(),
+        timer.elapsed(),
+    )
+}
+
+pub async fn crawl_all_projects_issues(config: Config) -> Result<()> {
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let client = ApiClient::new(&config)?;
+    let _metrics = MetricsServer::start(&config, &client).await?;
     let crawl_timer = Instant::now();
+    let mut stats = RunStats::new(&config).with_stages(&config.stages);
+    let current_user = required(&client, &mut stats, client.fetch_me().await?)?;
+    println!("Logged in as: {}", current_user.username);
 
-    // Gather stats
-    let mut total_reqs: u32 = 0;
-    let mut failed: u32 = 0;
-    let mut min_duration: u128 = 0;
-    let mut max_duration: u128 = 0;
-    let mut sum: u128 = 0;
+    let display = crawl_progress(&client, &config);
+    let timeseries = TimeSeriesWriter::start(&config, &client)?;
 
-    let mut has_more = true;
-    let mut page = 1;
+    let projects = collect_projects(&client, &mut stats).await?;
+    println!("Visible projects: {}", projects.len());
 
-    while has_more {
-        // Fetch listing
-        let listing = fetch_issues(&config, project_id.as_str(), page, 50).await?;
+    // A single schedule spans all projects so stages follow wall clock time
+    let mut schedule = crawl_schedule(&config);
 
-        has_more = false;
-        if listing.data.len() > 0 && listing.meta.total_records > 0 {
-            // Queue current batch
-            let mut handles = vec![];
-            for issue in listing.data {
-                let config_copy = config.clone();
-                let project_id_copy = project_id.clone();
-                let issue_id = issue.id.clone();
-                let handle = tokio::spawn(async move {
-                    fetch_issue(&config_copy, project_id_copy.as_str(), issue_id.as_str())
-                        .await
-                        .unwrap()
-                });
+    for project in projects {
+        if schedule
+            .as_ref()
+            .is_some_and(|schedule| schedule.is_finished())
+        {
+            client
+                .progress()
+                .println("Load profile finished, skipping remaining projects");
+            break;
+        }
+        client.progress().println(
+            format!(
+                "Crawling issues for project {}:{}",
+                project.key, project.name
+            )
+            .as_str(),
+        );
+        let project_stats =
+            crawl_issues(&client, &config, project.id.as_str(), schedule.as_mut()).await?;
+        stats.merge(&project_stats);
+    }
+    display.stop();
+    if let Some(timeseries) = timeseries {
+        timeseries.finish().await?;
+    }
 
-                handles.push(handle);
-            }
+    finish_run(
+        "crawl-all-issues",
+        &config,
+        started_at,
+        &stats,
+        crawl_timer.elapsed(),
+        timer.elapsed(),
+    )
+}
 
-            let req_count: u32 = handles.len().try_into().unwrap();
-            total_reqs += req_count;
+pub async fn crawl_issue_pages(config: Config) -> Result<()> {
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let client = ApiClient::new(&config)?;
+    let _metrics = MetricsServer::start(&config, &client).await?;
+    let crawl_timer = Instant::now();
+    let mut stats = RunStats::new(&config);
+    let current_user = required(&client, &mut stats, client.fetch_me().await?)?;
+    println!("Logged in as: {}", current_user.username);
 
-            // Process batch
-            for handle in handles {
-                let res = handle.await.unwrap();
-                if let None = res.data {
-                    failed += 1;
-                }
+    let project_id = config.project_id.as_str();
+    let project = required(&client, &mut stats, client.fetch_project(project_id).await?)?;
+    println!("{}: {}", project.key, project.name);
 
-                sum += res.duration;
+    let display = client.progress().display();
+    let timeseries = TimeSeriesWriter::start(&config, &client)?;
+    // Concurrency limits the pages loading at once, each page has its own connections
+    let mut pool: WorkerPool<IssuePage> = WorkerPool::new(config.concurrency);
 
-                if min_duration == 0 {
-                    min_duration = res.duration;
-                } else if res.duration < min_duration {
-                    min_duration = res.duration;
-                }
+    let mut page = 1;
+    loop {
+        let res = client.fetch_issues(project_id, page, 50).await?;
+        stats.record(&res);
 
-                if res.duration > max_duration {
-                    max_duration = res.duration;
-                }
-            }
+        // Stop paging when the listing keeps failing, already logged by the client
+        let Some(listing) = res.data else {
+            break;
+        };
 
-            //
-            // See if there are still more items
-            if listing.meta.total_pages > page {
-                page += 1;
-                has_more = true;
-            }
+        let meta = &listing.meta;
+        if page == 1 {
+            client.progress().add_total(
+                u64::from(meta.total_records) * ISSUE_PAGE_REQUESTS as u64
+                    + u64::from(meta.total_pages),
+            );
         }
-    }
 
-    let succeed = total_reqs - failed;
-    let big_success_ratio =
-        (BigDecimal::from(succeed) / BigDecimal::from(total_reqs)) * BigDecimal::from(100);
-    let success_ratio = big_success_ratio.round(2);
-    let big_sum = BigDecimal::from(sum);
-    let big_total_reqs = BigDecimal::from(total_reqs);
-    let big_avg = big_sum / big_total_reqs.clone();
-    let avg = big_avg.round(2);
-
-    let total_time = timer.elapsed().as_millis();
-    let total_crawl_time = crawl_timer.elapsed().as_millis();
-    let big_crawl_total_time = BigDecimal::from(total_crawl_time);
-    let big_rps: BigDecimal = big_total_reqs / (big_crawl_total_time / 1000.0);
-    let rps = big_rps.round(2);
-
-    // Print stats
-    println!("");
-    println!("Total requests: {}", total_reqs);
-    println!("Succeed: {}", succeed);
-    println!("Failed: {}", failed);
-    println!("Success rate: {}%", success_ratio);
-    println!("Min: {} ms", min_duration);
-    println!("Avg: {} ms", avg);
-    println!("Max: {} ms", max_duration);
-    println!("Requests per second: {}", rps);
-    println!("Run duration: {} ms", total_time);
+        for issue in listing.data.iter() {
+            let client_copy = client.clone();
+            let project_id_copy = project_id.to_string();
+            let issue_id = issue.id.clone();
+            pool.spawn(async move {
+                client_copy
+                    .fetch_issue_page_resources(project_id_copy.as_str(), issue_id.as_str())
+                    .await
+            })
+            .await;
+        }
+        // Record finished pages as we go instead of holding every response body
+        pool.join_until(tokio::time::Instant::now(), |page| stats.record_page(&page))
+            .await;
 
-    Ok(())
-}
+        if listing.data.is_empty() || meta.total_pages <= page {
+            break;
+        }
+        page += 1;
+    }
 
-pub async fn crawl_all_projects_issues(config: Config) -> Result<()> {
-    let timer = Instant::now();
-    let current_user = fetch_me(&config).await?;
-    println!("Logged in as: {}", current_user.username);
+    while let Some(page) = pool.join_next().await {
+        stats.record_page(&page);
+    }
+    display.stop();
+    if let Some(timeseries) = timeseries {
+        timeseries.finish().await?;
+    }
 
-    let projects = collect_projects(&config).await?;
-    println!("Visible projects: {}", projects.len());
+    finish_run(
+        "crawl-issue-pages",
+        &config,
+        started_at,
+        &stats,
+        crawl_timer.elapsed(),
+        timer.elapsed(),
+    )
+}
 
-    let crawl_timer = Instant::now();
+/// The crawl total grows as listings reveal how many issues there are
+fn crawl_progress(client: &ApiClient, config: &Config) -> ProgressDisplay {
+    let progress = client.progress();
+    if let Some(duration) = load_duration(config) {
+        progress.set_deadline(duration);
+    }
+    progress.display()
+}
 
-    // Gather stats
-    let mut total_reqs: u32 = 0;
-    let mut failed: u32 = 0;
-    let mut min_duration: u128 = 0;
-    let mut max_duration: u128 = 0;
-    let mut sum: u128 = 0;
+/// Crawls are only rate limited when a load profile is configured
+fn crawl_schedule(config: &Config) -> Option<ArrivalSchedule> {
+    if config.stages.is_empty() {
+        return None;
+    }
+    Some(ArrivalSchedule::from_stages(&config.stages))
+}
 
-    for project in projects {
-        println!(
-            "Crawling issues for project {}:{}",
-            project.key, project.name
-        );
-        let project_id = project.id;
-
-        let mut has_more = true;
-        let mut page = 1;
-
-        while has_more {
-            // Fetch listing
-            let listing = fetch_issues(&config, project_id.as_str(), page, 50).await?;
-
-            has_more = false;
-            if listing.data.len() > 0 && listing.meta.total_records > 0 {
-                // Queue current batch
-                let mut handles = vec![];
-                for issue in listing.data {
-                    let config_copy = config.clone();
-                    let project_id_copy = project_id.clone();
-                    let issue_id = issue.id.clone();
-                    let handle = tokio::spawn(async move {
-                        fetch_issue(&config_copy, project_id_copy.as_str(), issue_id.as_str())
-                            .await
-                            .unwrap()
-                    });
+/// Fetches every issue of the project through the paginated listing.
+/// With a schedule, issues are fetched at its rate and the crawl stops once it is over.
+async fn crawl_issues(
+    client: &ApiClient,
+    config: &Config,
+    project_id: &str,
+    mut schedule: Option<&mut ArrivalSchedule>,
+) -> Result<RunStats> {
+    let mut stats = RunStats::new(config).with_stages(&config.stages);
+    let mut pool: WorkerPool<IssueRequests> = WorkerPool::new(config.concurrency);
 
-                    handles.push(handle);
-                }
+    let mut has_more = true;
+    let mut page = 1;
 
-                let req_count: u32 = handles.len().try_into().unwrap();
-                total_reqs += req_count;
+    'pages: while has_more {
+        // Fetch listing
+        let res = client.fetch_issues(project_id, page, 50).await?;
+        stats.record(&res);
 
-                // Process batch
-                for handle in handles {
-                    let res = handle.await.unwrap();
-                    if let None = res.data {
-                        failed += 1;
-                    }
+        // Stop paging when the listing keeps failing, already logged by the client
+        let Some(listing) = res.data else {
+            break;
+        };
 
-                    sum += res.duration;
+        if page == 1 {
+            let meta = &listing.meta;
+            client
+                .progress()
+                .add_total(u64::from(meta.total_records) + u64::from(meta.total_pages));
+        }
 
-                    if min_duration == 0 {
-                        min_duration = res.duration;
-                    } else if res.duration < min_duration {
-                        min_duration = res.duration;
+        has_more = false;
+        if !listing.data.is_empty() && listing.meta.total_records > 0 {
+            // Queue current batch
+            for issue in listing.data {
+                let client_copy = client.clone();
+                let project_id_copy = project_id.to_string();
+                let issue_id = issue.id.clone();
+                let task = async move {
+                    client_copy
+                        .fetch_issue(project_id_copy.as_str(), issue_id.as_str())
+                        .await
+                        .unwrap()
+                };
+
+                match schedule.as_mut() {
+                    Some(schedule) => {
+                        let Some(arrival) = schedule.next_arrival() else {
+                            break 'pages;
+                        };
+                        pool.join_until(arrival.at, |crawl| crawl.record(&mut stats))
+                            .await;
+                        let task =
+                            crawl_issue(arrival.run(task), client, config, project_id, issue);
+                        if pool.queue(task) {
+                            stats.record_queued(arrival.stage);
+                        }
                     }
-
-                    if res.duration > max_duration {
-                        max_duration = res.duration;
+                    None => {
+                        let task = ScheduledResponse::unscheduled(task);
+                        pool.spawn(crawl_issue(task, client, config, project_id, issue))
+                            .await
                     }
                 }
+            }
 
-                // See if there are still more items
-                if listing.meta.total_pages > page {
-                    page += 1;
-                    has_more = true;
-                }
+            // See if there are still more items
+            if listing.meta.total_pages > page {
+                page += 1;
+                has_more = true;
             }
         }
     }
 
-    let succeed = total_reqs - failed;
-    let big_success_ratio =
-        (BigDecimal::from(succeed) / BigDecimal::from(total_reqs)) * BigDecimal::from(100);
-    let success_ratio = big_success_ratio.round(2);
-    let big_sum = BigDecimal::from(sum);
-    let big_total_reqs = BigDecimal::from(total_reqs);
-    let big_avg = big_sum / big_total_reqs.clone();
-    let avg = big_avg.round(2);
-
-    let total_time = timer.elapsed().as_millis();
-    let total_crawl_time = crawl_timer.elapsed().as_millis();
-    let big_crawl_total_time = BigDecimal::from(total_crawl_time);
-    let big_rps: BigDecimal = big_total_reqs / (big_crawl_total_time / 1000.0);
-    let rps = big_rps.round(2);
-
-    // Print stats
-    println!("");
-    println!("Total requests: {}", total_reqs);
-    println!("Succeed: {}", succeed);
-    println!("Failed: {}", failed);
-    println!("Success rate: {}%", success_ratio);
-    println!("Min: {} ms", min_duration);
-    println!("Avg: {} ms", avg);
-    println!("Max: {} ms", max_duration);
-    println!("Requests per second: {}", rps);
-    println!("Run duration: {} ms", total_time);
+    // Gather stats
+    while let Some(crawl) = pool.join_next().await {
+        crawl.record(&mut stats);
+    }
 
-    Ok(())
+    Ok(stats)
 }
 
-async fn collect_projects(config: &Config) -> Result<Vec<ProjectSlim>> {
+async fn collect_projects(client: &ApiClient, stats: &mut RunStats) -> Result<Vec<ProjectSlim>> {
     let mut ids: Vec<ProjectSlim> = Vec::new();
 
     let mut has_more = true;
     let mut page = 1;
 
     while has_more {
-        let listing: PaginationResult<Project> = fetch_projects(config, page, 50).await?;
+        let res = client.fetch_projects(page, 50).await?;
+        stats.record(&res);
+
+        // Stop paging when the listing keeps failing, already logged by the client
+        let Some(listing) = res.data else {
+            break;
+        };
+
+        if page == 1 {
+            client
+                .progress()
+                .add_total(u64::from(listing.meta.total_pages));
+        }
+
         has_more = false;
-        if listing.data.len() > 0 && listing.meta.total_records > 0 {
+        if !listing.data.is_empty() && listing.meta.total_records > 0 {
             for project in listing.data {
                 ids.push(ProjectSlim {
                     id: project.id,
@@ -452,3 +764,165 @@ async fn collect_projects(config: &Config) -> Result<Vec<ProjectSlim>> {
 
     Ok(ids)
 }
+
+pub async fn run_scenario(config: Config, args: &ScenarioArgs) -> Result<()> {
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let mut scenario = Scenario::load(args.file.as_path())?;
+    if let Some(users) = args.users {
+        scenario.users = users;
+    }
+    if let Some(iterations) = args.iterations {
+        scenario.iterations = iterations;
+    }
+    println!("Scenario: {}", scenario.name);
+
+    let client = ApiClient::new(&config)?;
+    let _metrics = MetricsServer::start(&config, &client).await?;
+    let scenario_timer = Instant::now();
+    let mut stats = RunStats::new(&config);
+    let current_user = required(&client, &mut stats, client.fetch_me().await?)?;
+    println!("Logged in as: {}", current_user.username);
+
+    let mut variables = scenario.variables.clone();
+    variables
+        .entry(String::from("project_id"))
+        .or_insert(config.project_id.clone());
+
+    let progress = client.progress();
+    if let Some(duration) = config.duration {
+        progress.set_deadline(duration);
+    }
+    let display = progress.display();
+    let timeseries = TimeSeriesWriter::start(&config, &client)?;
+
+    let started = tokio::time::Instant::now();
+    let deadline = config.duration.map(|duration| started + duration);
+    let users = scenario.users;
+    let scenario = Arc::new(scenario);
+    let mut pool: WorkerPool<UserResult> = WorkerPool::new(users as usize);
+    for user in 0..users {
+        // Users start evenly spread over the ramp up
+        if let Some(ramp_up) = scenario.ramp_up {
+            tokio::time::sleep_until(started + ramp_up * user / users).await;
+        }
+        pool.spawn(scenario::run_user(
+            client.clone(),
+            scenario.clone(),
+            variables.clone(),
+            deadline,
+            RunStats::new(&config),
+        ))
+        .await;
+    }
+
+    let mut completed: u64 = 0;
+    let mut aborted: u64 = 0;
+    while let Some(result) = pool.join_next().await {
+        stats.merge(&result.stats);
+        completed += result.completed;
+        aborted += result.aborted;
+    }
+    display.stop();
+    if let Some(timeseries) = timeseries {
+        timeseries.finish().await?;
+    }
+
+    println!("Iterations: {} completed, {} aborted", completed, aborted);
+
+    finish_run(
+        "scenario",
+        &config,
+        started_at,
+        &stats,
+        scenario_timer.elapsed(),
+        timer.elapsed(),
+    )
+}
+
+/// An issue request along with the requests that followed it, ie: its comments
+struct IssueRequests {
+    issue: ScheduledResponse<Issue>,
+    related: Vec<ResponseData<()>>,
+}
+
+impl IssueRequests {
+    fn record(&self, stats: &mut RunStats) {
+        stats.record_scheduled(&self.issue);
+        for res in self.related.iter() {
+            stats.record(res);
+        }
+    }
+}
+
+/// Fetches the issue then every page of the included resources
+fn crawl_issue(
+    task: impl Future<Output = ScheduledResponse<Issue>>,
+    client: &ApiClient,
+    config: &Config,
+    project_id: &str,
+    issue: Issue,
+) -> impl Future<Output = IssueRequests> {
+    let client = client.clone();
+    let include = config.include.clone();
+    let project_id = project_id.to_string();
+    async move {
+        let issue_res = task.await;
+        let mut related: Vec<ResponseData<()>> = Vec::new();
+        // Nothing to follow when the issue itself failed
+        if issue_res.res.data.is_some() {
+            for resource in include {
+                crawl_related(&client, &project_id, &issue.id, resource, &mut related).await;
+            }
+        }
+        IssueRequests {
+            issue: issue_res,
+            related,
+        }
+    }
+}
+
+/// Follows the pagination of an issue's comments or timeline until the last page
+async fn crawl_related(
+    client: &ApiClient,
+    project_id: &str,
+    issue_id: &str,
+    resource: CrawlInclude,
+    related: &mut Vec<ResponseData<()>>,
+) {
+    let mut page = 1;
+    loop {
+        let (total_pages, res) = match resource {
+            CrawlInclude::Comments => page_count(
+                client
+                    .fetch_issue_comments(project_id, issue_id, page, 50)
+                    .await
+                    .unwrap(),
+            ),
+            CrawlInclude::Timeline => page_count(
+                client
+                    .fetch_issue_timeline_items(project_id, issue_id, page, 50)
+                    .await
+                    .unwrap(),
+            ),
+        };
+        related.push(res);
+
+        // Stop paging when the listing keeps failing, already logged by the client
+        let Some(total_pages) = total_pages else {
+            break;
+        };
+        if page == 1 {
+            client.progress().add_total(u64::from(total_pages));
+        }
+        if total_pages <= page {
+            break;
+        }
+        page += 1;
+    }
+}
+
+fn page_count<T>(res: ResponseData<PaginationResult<T>>) -> (Option<u32>, ResponseData<()>) {
+    let total_pages = res.data.as_ref().map(|listing| listing.meta.total_pages);
+    (total_pages, res.without_data())
+}
@@ -0,0 +1,504 @@
+use anyhow::{anyhow, bail, Context};
+use rand::Rng;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::config::humantime_option;
+use crate::crawler::{find_endpoint, ApiClient, EndpointSpec, ResponseData};
+use crate::error::Result;
+use crate::stats::RunStats;
+
+/// Scenario file as written by the user
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ScenarioFile {
+    name: String,
+    #[serde(default = "default_users")]
+    users: u32,
+    #[serde(default = "default_iterations")]
+    iterations: u32,
+    #[serde(default, with = "humantime_option")]
+    ramp_up: Option<Duration>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+    journeys: Vec<JourneyFile>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct JourneyFile {
+    name: String,
+    #[serde(default = "default_weight")]
+    weight: u32,
+    steps: Vec<StepFile>,
+}
+
+/// Either a single `request` or a group of `parallel` requests
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct StepFile {
+    request: Option<String>,
+    #[serde(default)]
+    query: BTreeMap<String, String>,
+    #[serde(default)]
+    extract: BTreeMap<String, String>,
+    #[serde(default)]
+    parallel: Vec<RequestFile>,
+    think_time: Option<ThinkTime>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RequestFile {
+    request: String,
+    #[serde(default)]
+    query: BTreeMap<String, String>,
+    #[serde(default)]
+    extract: BTreeMap<String, String>,
+}
+
+fn default_users() -> u32 {
+    1
+}
+
+fn default_iterations() -> u32 {
+    1
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// User journeys to replay against the API, see `scenario-example.toml`
+#[derive(Debug)]
+pub struct Scenario {
+    pub name: String,
+    pub users: u32,
+    /// Journeys run by each virtual user, ignored when a duration is configured
+    pub iterations: u32,
+    /// Virtual users start evenly spread over this time
+    pub ramp_up: Option<Duration>,
+    pub variables: BTreeMap<String, String>,
+    pub journeys: Vec<Journey>,
+}
+
+#[derive(Debug)]
+pub struct Journey {
+    pub name: String,
+    pub weight: u32,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug)]
+pub struct Step {
+    /// Sent together, the next step starts once all of them completed
+    pub requests: Vec<Request>,
+    pub think_time: Option<ThinkTime>,
+}
+
+#[derive(Debug)]
+pub struct Request {
+    pub endpoint: &'static EndpointSpec,
+    /// Added to or replacing the endpoint's default query, values may use `{variable}`
+    pub query: BTreeMap<String, String>,
+    /// Variable name to path into the response body, ie: `data[*].id`
+    pub extract: BTreeMap<String, String>,
+}
+
+/// Pause between steps, either fixed ("2s") or random within a range ("1s..3s")
+#[derive(Clone, Copy, Debug)]
+pub struct ThinkTime {
+    min: Duration,
+    max: Duration,
+}
+
+impl<'de> Deserialize<'de> for ThinkTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        let parse =
+            |value: &str| humantime::parse_duration(value.trim()).map_err(de::Error::custom);
+        let (min, max) = match value.split_once("..") {
+            Some((min, max)) => (parse(min)?, parse(max)?),
+            None => {
+                let duration = parse(&value)?;
+                (duration, duration)
+            }
+        };
+        if min > max {
+            return Err(de::Error::custom(
+                "think time range must go from low to high",
+            ));
+        }
+        Ok(ThinkTime { min, max })
+    }
+}
+
+impl ThinkTime {
+    fn pick(&self) -> Duration {
+        if self.min == self.max {
+            return self.min;
+        }
+        rand::thread_rng().gen_range(self.min..=self.max)
+    }
+}
+
+impl Scenario {
+    pub fn load(filename: &Path) -> Result<Scenario> {
+        let contents = fs::read_to_string(filename)
+            .with_context(|| format!("Unable to read scenario file {}", filename.display()))?;
+        let file: ScenarioFile = toml::from_str(contents.as_str())
+            .with_context(|| format!("Unable to parse scenario file {}", filename.display()))?;
+
+        if file.users == 0 {
+            bail!("Scenario users must be at least 1");
+        }
+        if file.iterations == 0 {
+            bail!("Scenario iterations must be at least 1");
+        }
+        if file.journeys.is_empty() {
+            bail!("Scenario must have at least one journey");
+        }
+
+        let mut journeys: Vec<Journey> = Vec::new();
+        for journey in file.journeys {
+            if journey.weight == 0 {
+                bail!("Journey {} must have a weight of at least 1", journey.name);
+            }
+            if journey.steps.is_empty() {
+                bail!("Journey {} must have at least one step", journey.name);
+            }
+
+            let mut steps: Vec<Step> = Vec::new();
+            for step in journey.steps {
+                let requests = match (step.request, step.parallel.is_empty()) {
+                    (Some(request), true) => vec![RequestFile {
+                        request,
+                        query: step.query,
+                        extract: step.extract,
+                    }],
+                    (None, false) if step.query.is_empty() && step.extract.is_empty() => {
+                        step.parallel
+                    }
+                    _ => bail!(
+                        "Steps of journey {} need either a request or a parallel group",
+                        journey.name
+                    ),
+                };
+
+                let requests = requests
+                    .into_iter()
+                    .map(|request| {
+                        let endpoint =
+                            find_endpoint(request.request.as_str()).ok_or_else(|| {
+                                anyhow!(
+                                    "Unknown endpoint {} in journey {}",
+                                    request.request,
+                                    journey.name
+                                )
+                            })?;
+                        Ok(Request {
+                            endpoint,
+                            query: request.query,
+                            extract: request.extract,
+                        })
+                    })
+                    .collect::<Result<Vec<Request>>>()?;
+
+                steps.push(Step {
+                    requests,
+                    think_time: step.think_time,
+                });
+            }
+
+            journeys.push(Journey {
+                name: journey.name,
+                weight: journey.weight,
+                steps,
+            });
+        }
+
+        Ok(Scenario {
+            name: file.name,
+            users: file.users,
+            iterations: file.iterations,
+            ramp_up: file.ramp_up,
+            variables: file.variables,
+            journeys,
+        })
+    }
+
+    /// Picks a journey at random, proportionally to the weights
+    fn pick_journey(&self) -> &Journey {
+        let total: u32 = self.journeys.iter().map(|journey| journey.weight).sum();
+        let mut roll = rand::thread_rng().gen_range(0..total);
+        for journey in self.journeys.iter() {
+            if roll < journey.weight {
+                return journey;
+            }
+            roll -= journey.weight;
+        }
+        &self.journeys[self.journeys.len() - 1]
+    }
+}
+
+/// Outcome of a single virtual user
+pub struct UserResult {
+    pub stats: RunStats,
+    pub completed: u64,
+    /// Iterations stopped early by a failed request or a missing variable
+    pub aborted: u64,
+}
+
+/// Runs journeys until the iterations are done or the deadline has passed
+pub async fn run_user(
+    client: ApiClient,
+    scenario: Arc<Scenario>,
+    variables: BTreeMap<String, String>,
+    deadline: Option<Instant>,
+    stats: RunStats,
+) -> UserResult {
+    let mut result = UserResult {
+        stats,
+        completed: 0,
+        aborted: 0,
+    };
+
+    let mut iteration: u32 = 0;
+    loop {
+        match deadline {
+            Some(deadline) if Instant::now() >= deadline => break,
+            None if iteration >= scenario.iterations => break,
+            _ => {}
+        }
+        iteration += 1;
+
+        let journey = scenario.pick_journey();
+        let mut vars = variables.clone();
+        match run_journey(&client, journey, &mut vars, deadline, &mut result.stats).await {
+            Ok(()) => result.completed += 1,
+            Err(err) => {
+                client.print_error(
+                    format!("Journey {} aborted. Error: {}", journey.name, err).as_str(),
+                );
+                result.aborted += 1;
+            }
+        }
+    }
+
+    result
+}
+
+async fn run_journey(
+    client: &ApiClient,
+    journey: &Journey,
+    vars: &mut BTreeMap<String, String>,
+    deadline: Option<Instant>,
+    stats: &mut RunStats,
+) -> Result<()> {
+    for step in journey.steps.iter() {
+        let mut tasks: JoinSet<(usize, ResponseData<Value>)> = JoinSet::new();
+        for (index, request) in step.requests.iter().enumerate() {
+            let path = render(request.endpoint.path, vars)?;
+            let query = build_query(request, vars)?;
+            let client = client.clone();
+            let endpoint = request.endpoint;
+            tasks.spawn(async move {
+                (
+                    index,
+                    client.fetch_endpoint(endpoint, path.as_str(), &query).await,
+                )
+            });
+        }
+
+        let mut responses: Vec<Option<ResponseData<Value>>> =
+            step.requests.iter().map(|_| None).collect();
+        while let Some(res) = tasks.join_next().await {
+            let (index, res) = res?;
+            stats.record(&res);
+            responses[index] = Some(res);
+        }
+
+        for (request, res) in step.requests.iter().zip(responses) {
+            let Some(body) = res.and_then(|res| res.data) else {
+                bail!("{} failed", request.endpoint.name);
+            };
+            for (name, path) in request.extract.iter() {
+                let value = extract(&body, path).ok_or_else(|| {
+                    anyhow!(
+                        "Nothing to extract at {} from {}",
+                        path,
+                        request.endpoint.name
+                    )
+                })?;
+                vars.insert(name.clone(), value);
+            }
+        }
+
+        if let Some(think_time) = step.think_time {
+            let wake = Instant::now() + think_time.pick();
+            // Never sleep past the end of the run
+            tokio::time::sleep_until(deadline.map_or(wake, |deadline| wake.min(deadline))).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Endpoint defaults overridden by the step's query, both with variables filled in
+fn build_query(
+    request: &Request,
+    vars: &BTreeMap<String, String>,
+) -> Result<Vec<(String, String)>> {
+    let mut query: BTreeMap<&str, &str> = request.endpoint.query.iter().copied().collect();
+    for (key, value) in request.query.iter() {
+        query.insert(key.as_str(), value.as_str());
+    }
+    query
+        .into_iter()
+        .map(|(key, value)| Ok((key.to_string(), render(value, vars)?)))
+        .collect()
+}
+
+/// Replaces every `{name}` with the variable's value
+fn render(template: &str, vars: &BTreeMap<String, String>) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + end];
+        let value = vars
+            .get(name)
+            .ok_or_else(|| anyhow!("Variable {} is not defined", name))?;
+        out.push_str(&rest[..start]);
+        out.push_str(value);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Follows a path like `data[*].id`, `data[0].key` or `meta.total_records`.
+/// When `[*]` matches several values one of them is picked at random.
+fn extract(body: &Value, path: &str) -> Option<String> {
+    let mut current: Vec<&Value> = vec![body];
+    for segment in path.split('.') {
+        let (name, index) = match segment.split_once('[') {
+            Some((name, index)) => (name, Some(index.strip_suffix(']')?)),
+            None => (segment, None),
+        };
+
+        if !name.is_empty() {
+            current = current
+                .into_iter()
+                .filter_map(|value| value.get(name))
+                .collect();
+        }
+        current = match index {
+            Some("*") => current
+                .into_iter()
+                .filter_map(Value::as_array)
+                .flatten()
+                .collect(),
+            Some(index) => {
+                let index: usize = index.parse().ok()?;
+                current
+                    .into_iter()
+                    .filter_map(|value| value.get(index))
+                    .collect()
+            }
+            None => current,
+        };
+    }
+
+    if current.is_empty() {
+        return None;
+    }
+    let value = current[rand::thread_rng().gen_range(0..current.len())];
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn render_fills_every_variable() {
+        let vars = vars(&[("project_id", "p1"), ("issue_id", "i1")]);
+        let path = render("/projects/{project_id}/issues/{issue_id}", &vars).unwrap();
+        assert_eq!(path, "/projects/p1/issues/i1");
+        assert_eq!(render("no variables", &vars).unwrap(), "no variables");
+    }
+
+    #[test]
+    fn render_rejects_unknown_variables() {
+        let vars = vars(&[("project_id", "p1")]);
+        assert!(render("/projects/{missing}", &vars).is_err());
+        assert!(render("/projects/{}", &vars).is_err());
+    }
+
+    #[test]
+    fn render_keeps_an_unterminated_brace() {
+        let vars = vars(&[("project_id", "p1")]);
+        let path = render("/projects/{project_id}/{oops", &vars).unwrap();
+        assert_eq!(path, "/projects/p1/{oops");
+    }
+
+    #[test]
+    fn extract_follows_fields_and_indexes() {
+        let body = json!({
+            "data": [{"id": "a", "key": "K-1"}, {"id": "b", "key": "K-2"}],
+            "meta": {"total_records": 42, "next": null},
+        });
+        assert_eq!(extract(&body, "data[1].key").as_deref(), Some("K-2"));
+        assert_eq!(extract(&body, "meta.total_records").as_deref(), Some("42"));
+        assert_eq!(extract(&body, "meta.next"), None);
+        assert_eq!(extract(&body, "meta.missing"), None);
+
+        let listing = json!([{"id": "a"}]);
+        assert_eq!(extract(&listing, "[0].id").as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn extract_picks_one_of_the_wildcard_matches() {
+        let body = json!({"data": [{"id": "a"}, {"id": "b"}, {"name": "no id"}]});
+        for _ in 0..20 {
+            let id = extract(&body, "data[*].id").unwrap();
+            assert!(id == "a" || id == "b", "unexpected {}", id);
+        }
+    }
+
+    #[test]
+    fn extract_rejects_bad_paths() {
+        let body = json!({"data": [{"id": "a"}], "meta": {"total": 1}});
+        // Wildcard over something that is not an array
+        assert_eq!(extract(&body, "meta[*]"), None);
+        assert_eq!(extract(&body, "data[*].id[*]"), None);
+        // Bad or out of range indexes
+        assert_eq!(extract(&body, "data[x].id"), None);
+        assert_eq!(extract(&body, "data[-1].id"), None);
+        assert_eq!(extract(&body, "data[3].id"), None);
+        assert_eq!(extract(&body, "data[0.id"), None);
+    }
+}
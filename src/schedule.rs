@@ -0,0 +1,295 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::Stage;
+use crate::crawler::ResponseData;
+
+/// When a scheduled request should be sent and the stage it belongs to
+#[derive(Clone, Copy, Debug)]
+pub struct Arrival {
+    pub at: Instant,
+    pub stage: Option<usize>,
+}
+
+/// Result of a request that may have been sent on a schedule
+pub struct ScheduledResponse<T> {
+    pub stage: Option<usize>,
+    /// Time from the intended send time until the response arrived, includes
+    /// any time the request spent queued for a free worker
+    pub response_time: Option<Duration>,
+    pub res: ResponseData<T>,
+}
+
+impl<T> ScheduledResponse<T> {
+    /// Runs a request that is not bound to a schedule
+    pub async fn unscheduled<F>(task: F) -> ScheduledResponse<T>
+    where
+        F: Future<Output = ResponseData<T>>,
+    {
+        ScheduledResponse {
+            stage: None,
+            response_time: None,
+            res: task.await,
+        }
+    }
+}
+
+impl Arrival {
+    /// Runs the request, measuring its response time from the intended send time.
+    /// Await it inside the worker so that time spent queued for a slot is counted.
+    pub async fn run<T, F>(self, task: F) -> ScheduledResponse<T>
+    where
+        F: Future<Output = ResponseData<T>>,
+    {
+        let res = task.await;
+        ScheduledResponse {
+            stage: self.stage,
+            response_time: Some(self.at.elapsed()),
+            res,
+        }
+    }
+}
+
+/// Part of the schedule where the rate changes linearly
+struct Segment {
+    stage: Option<usize>,
+    duration: Option<Duration>,
+    from_rps: f64,
+    to_rps: f64,
+}
+
+/// Open model arrival schedule, computes the intended send time of every request
+/// independently of how long earlier requests took
+pub struct ArrivalSchedule {
+    start: Instant,
+    segments: Vec<Segment>,
+    /// Index of the segment holding the next arrival
+    segment: usize,
+    /// Offset and arrival count at the beginning of the current segment
+    segment_offset: Duration,
+    segment_count: f64,
+    scheduled: u64,
+    limit: Option<u64>,
+    finished: bool,
+}
+
+impl ArrivalSchedule {
+    /// Fixed rate for the given duration, or until `limit` requests without one
+    pub fn constant(rps: f64, duration: Option<Duration>, limit: Option<u64>) -> ArrivalSchedule {
+        let segment = Segment {
+            stage: None,
+            duration,
+            from_rps: rps,
+            to_rps: rps,
+        };
+        ArrivalSchedule::new(vec![segment], limit)
+    }
+
+    /// Rate ramping linearly between the targets of consecutive stages
+    pub fn from_stages(stages: &[Stage]) -> ArrivalSchedule {
+        let mut from_rps = 0.0;
+        let segments = stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| {
+                let segment = Segment {
+                    stage: Some(index),
+                    duration: Some(stage.duration),
+                    from_rps,
+                    to_rps: stage.target_rps,
+                };
+                from_rps = stage.target_rps;
+                segment
+            })
+            .collect();
+        ArrivalSchedule::new(segments, None)
+    }
+
+    fn new(segments: Vec<Segment>, limit: Option<u64>) -> ArrivalSchedule {
+        ArrivalSchedule {
+            start: Instant::now(),
+            segments,
+            segment: 0,
+            segment_offset: Duration::ZERO,
+            segment_count: 0.0,
+            scheduled: 0,
+            limit,
+            finished: false,
+        }
+    }
+
+    /// Next arrival, None once the schedule is over
+    pub fn next_arrival(&mut self) -> Option<Arrival> {
+        let arrival = self.compute_next();
+        self.finished = arrival.is_none();
+        arrival
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn compute_next(&mut self) -> Option<Arrival> {
+        if self.limit.is_some_and(|limit| self.scheduled >= limit) {
+            return None;
+        }
+
+        while let Some(segment) = self.segments.get(self.segment) {
+            let count = (self.scheduled as f64 - self.segment_count).max(0.0);
+            if let Some(offset) = segment.offset_of(count) {
+                self.scheduled += 1;
+                return Some(Arrival {
+                    at: self.start + self.segment_offset + offset,
+                    stage: segment.stage,
+                });
+            }
+
+            // Segment exhausted, continue with the next one
+            let duration = segment.duration?;
+            self.segment_offset += duration;
+            self.segment_count +=
+                (segment.from_rps + segment.to_rps) / 2.0 * duration.as_secs_f64();
+            self.segment += 1;
+        }
+
+        None
+    }
+}
+
+impl Segment {
+    /// Time into the segment where `count` arrivals have accumulated.
+    /// Solves `from_rps * t + slope * t^2 / 2 = count` in a form that also holds for flat segments.
+    fn offset_of(&self, count: f64) -> Option<Duration> {
+        if self.from_rps == 0.0 && self.to_rps == 0.0 {
+            return None;
+        }
+        let seconds = self.duration.map(|duration| duration.as_secs_f64());
+        let slope = match seconds {
+            Some(seconds) => (self.to_rps - self.from_rps) / seconds,
+            None => 0.0,
+        };
+
+        let discriminant = self.from_rps * self.from_rps + 2.0 * slope * count;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let denominator = self.from_rps + discriminant.sqrt();
+        let offset = if count == 0.0 {
+            0.0
+        } else if denominator > 0.0 {
+            2.0 * count / denominator
+        } else {
+            return None;
+        };
+
+        match seconds {
+            Some(seconds) if offset >= seconds => None,
+            _ => Some(Duration::from_secs_f64(offset)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Offsets from the schedule start, in seconds, and the stage of every arrival
+    fn drain(schedule: &mut ArrivalSchedule) -> Vec<(f64, Option<usize>)> {
+        let start = schedule.start;
+        let mut arrivals = Vec::new();
+        while let Some(arrival) = schedule.next_arrival() {
+            arrivals.push(((arrival.at - start).as_secs_f64(), arrival.stage));
+            assert!(arrivals.len() <= 10_000, "schedule never ends");
+        }
+        arrivals
+    }
+
+    fn stage(seconds: u64, target_rps: f64) -> Stage {
+        Stage {
+            duration: Duration::from_secs(seconds),
+            target_rps,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn constant_rate_stops_at_the_limit() {
+        let mut schedule = ArrivalSchedule::constant(4.0, None, Some(3));
+        let arrivals = drain(&mut schedule);
+
+        assert_eq!(arrivals.len(), 3);
+        for (index, (offset, stage)) in arrivals.iter().enumerate() {
+            assert_close(*offset, index as f64 * 0.25);
+            assert_eq!(*stage, None);
+        }
+        assert!(schedule.is_finished());
+        assert!(schedule.next_arrival().is_none());
+    }
+
+    #[test]
+    fn constant_rate_stops_at_the_duration() {
+        let mut schedule = ArrivalSchedule::constant(10.0, Some(Duration::from_secs(1)), None);
+        let arrivals = drain(&mut schedule);
+
+        assert_eq!(arrivals.len(), 10);
+        assert_close(arrivals[9].0, 0.9);
+    }
+
+    #[test]
+    fn zero_rate_has_no_arrivals() {
+        let mut bounded = ArrivalSchedule::constant(0.0, Some(Duration::from_secs(5)), None);
+        assert!(drain(&mut bounded).is_empty());
+        assert!(bounded.is_finished());
+
+        let mut unbounded = ArrivalSchedule::constant(0.0, None, None);
+        assert!(drain(&mut unbounded).is_empty());
+    }
+
+    #[test]
+    fn stages_ramp_then_hold() {
+        let mut schedule = ArrivalSchedule::from_stages(&[stage(2, 10.0), stage(1, 10.0)]);
+        let arrivals = drain(&mut schedule);
+
+        // Ramp from 0 to 10 rps over 2s then 10 rps for 1s
+        assert_eq!(arrivals.len(), 20);
+        assert!(arrivals[..10].iter().all(|(_, stage)| *stage == Some(0)));
+        assert!(arrivals[10..].iter().all(|(_, stage)| *stage == Some(1)));
+        assert!(arrivals.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+
+        // The ramp is slow at first, the k-th arrival is at sqrt(2k / slope)
+        assert_close(arrivals[0].0, 0.0);
+        assert_close(arrivals[5].0, 2.0_f64.sqrt());
+        // The second stage starts exactly at its boundary
+        assert_close(arrivals[10].0, 2.0);
+        assert_close(arrivals[19].0, 2.9);
+    }
+
+    #[test]
+    fn idle_stage_is_skipped() {
+        let mut schedule = ArrivalSchedule::from_stages(&[stage(1, 0.0), stage(1, 10.0)]);
+        let arrivals = drain(&mut schedule);
+
+        assert_eq!(arrivals.len(), 5);
+        assert!(arrivals.iter().all(|(_, stage)| *stage == Some(1)));
+        assert_close(arrivals[0].0, 1.0);
+    }
+
+    #[test]
+    fn ramp_down_ends_with_the_last_stage() {
+        let mut schedule = ArrivalSchedule::from_stages(&[stage(1, 10.0), stage(2, 0.0)]);
+        let arrivals = drain(&mut schedule);
+
+        // 5 arrivals ramping up, 10 more ramping down to zero
+        assert_eq!(arrivals.len(), 15);
+        assert!(arrivals.iter().all(|(offset, _)| *offset < 3.0));
+    }
+}
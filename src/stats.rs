@@ -0,0 +1,647 @@
+use bigdecimal::BigDecimal;
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::{Config, Stage};
+use crate::crawler::{IssuePage, ResponseData};
+use crate::schedule::ScheduledResponse;
+
+const HISTOGRAM_BUCKETS: u64 = 10;
+const HISTOGRAM_BAR_WIDTH: u64 = 40;
+const PERCENTILES: [f64; 5] = [50.0, 90.0, 95.0, 99.0, 99.9];
+
+/// Latency recorder with microsecond precision
+#[derive(Clone, Debug)]
+pub struct LatencyHistogram {
+    histogram: Histogram<u64>,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram::new()
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> LatencyHistogram {
+        LatencyHistogram {
+            // 3 significant digits, auto-resizes as larger values are recorded
+            histogram: Histogram::new(3).expect("Invalid histogram precision"),
+        }
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        self.histogram
+            .add(&other.histogram)
+            .expect("Unable to merge latency histograms");
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        self.histogram
+            .record(micros)
+            .expect("Unable to record latency");
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    pub fn min(&self) -> Duration {
+        Duration::from_micros(self.histogram.min())
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.histogram.max())
+    }
+
+    pub fn mean(&self) -> Duration {
+        Duration::from_secs_f64(self.histogram.mean() / 1_000_000.0)
+    }
+
+    pub fn stdev(&self) -> Duration {
+        Duration::from_secs_f64(self.histogram.stdev() / 1_000_000.0)
+    }
+
+    /// Number of recorded values up to and including the limit, used for Prometheus buckets
+    pub fn count_at_most(&self, limit: Duration) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        let micros = u64::try_from(limit.as_micros()).unwrap_or(u64::MAX);
+        self.histogram.count_between(0, micros)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    /// Latency at the given percentile, between 0 and 100
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        Duration::from_micros(self.histogram.value_at_percentile(percentile))
+    }
+
+    pub fn print_summary(&self) {
+        println!("Min: {}", format_ms(self.min()));
+        println!("Avg: {}", format_ms(self.mean()));
+        println!("Max: {}", format_ms(self.max()));
+        println!("Std dev: {}", format_ms(self.stdev()));
+        for percentile in PERCENTILES {
+            println!(
+                "p{}: {}",
+                percentile,
+                format_ms(self.percentile(percentile))
+            );
+        }
+    }
+
+    /// Counts per equal width bucket between min and max, keyed by the bucket's upper bound
+    pub fn distribution(&self, bucket_limit: u64) -> Vec<(Duration, u64)> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        let min = self.histogram.min();
+        let max = self.histogram.max();
+        let width = ((max - min) / bucket_limit).max(1);
+        let bucket_count = ((max - min) / width + 1).min(bucket_limit) as usize;
+
+        let mut counts: Vec<u64> = vec![0; bucket_count];
+        for value in self.histogram.iter_recorded() {
+            let offset = value.value_iterated_to().clamp(min, max) - min;
+            let index = ((offset / width) as usize).min(bucket_count - 1);
+            counts[index] += value.count_at_value();
+        }
+
+        counts
+            .into_iter()
+            .enumerate()
+            .map(|(index, count)| {
+                let bound = if index == bucket_count - 1 {
+                    max
+                } else {
+                    min + width * (index as u64 + 1)
+                };
+                (Duration::from_micros(bound), count)
+            })
+            .collect()
+    }
+
+    /// Prints counts for equal width latency buckets between min and max
+    pub fn print_distribution(&self) {
+        let buckets = self.distribution(HISTOGRAM_BUCKETS);
+        if buckets.is_empty() {
+            return;
+        }
+
+        let max_count = buckets.iter().map(|(_, count)| *count).max().unwrap_or(0);
+
+        println!("Latency distribution:");
+        for (bound, count) in buckets {
+            let bar_len = (count * HISTOGRAM_BAR_WIDTH)
+                .checked_div(max_count)
+                .unwrap_or(0);
+            println!(
+                "  {:>12} [{:>6}] |{}",
+                format_ms(bound),
+                count,
+                "#".repeat(bar_len as usize)
+            );
+        }
+    }
+}
+
+/// Formats a duration as milliseconds with microsecond precision
+pub fn format_ms(duration: Duration) -> String {
+    format!("{:.3} ms", as_ms(duration))
+}
+
+pub fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Milliseconds rounded to microsecond precision
+pub fn round_ms(duration: Duration) -> f64 {
+    (as_ms(duration) * 1000.0).round() / 1000.0
+}
+
+pub fn unix_millis(time: SystemTime) -> u64 {
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis();
+    u64::try_from(millis).unwrap_or(u64::MAX)
+}
+
+/// Counters and latencies of a single logical endpoint
+#[derive(Clone, Debug, Default)]
+pub struct EndpointStats {
+    pub total: u64,
+    pub failed: u64,
+    /// Failure counts by status code or failure kind
+    pub errors: BTreeMap<String, u64>,
+    pub latencies: LatencyHistogram,
+}
+
+impl EndpointStats {
+    fn merge(&mut self, other: &EndpointStats) {
+        self.total += other.total;
+        self.failed += other.failed;
+        for (group, count) in other.errors.iter() {
+            *self.errors.entry(group.clone()).or_insert(0) += count;
+        }
+        self.latencies.merge(&other.latencies);
+    }
+}
+
+/// Requests sent within the same second
+#[derive(Clone, Debug, Default)]
+pub struct TimelineBucket {
+    pub total: u64,
+    pub failed: u64,
+    /// Raw latencies in microseconds so that adjacent seconds can be combined exactly
+    pub latencies_us: Vec<u32>,
+}
+
+impl TimelineBucket {
+    pub fn merge(&mut self, other: &TimelineBucket) {
+        self.total += other.total;
+        self.failed += other.failed;
+        self.latencies_us.extend_from_slice(&other.latencies_us);
+    }
+}
+
+/// Scheduled requests of a single load profile stage
+#[derive(Clone, Debug)]
+pub struct StageStats {
+    pub duration: Duration,
+    pub target_rps: f64,
+    pub total: u64,
+    pub failed: u64,
+    pub queued: u64,
+    pub latencies: LatencyHistogram,
+    pub response_times: LatencyHistogram,
+}
+
+impl StageStats {
+    fn merge(&mut self, other: &StageStats) {
+        self.total += other.total;
+        self.failed += other.failed;
+        self.queued += other.queued;
+        self.latencies.merge(&other.latencies);
+        self.response_times.merge(&other.response_times);
+    }
+}
+
+/// Failures sharing the same status code or failure kind
+#[derive(Clone, Debug)]
+pub struct ErrorGroup {
+    pub count: u64,
+    /// Response body or error message of the first failure in the group
+    pub sample: String,
+}
+
+/// A single request result, only kept when samples are requested in the report
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sample {
+    pub endpoint: String,
+    /// Unix timestamp in milliseconds when the request was sent
+    pub timestamp_ms: u64,
+    pub duration_ms: f64,
+    pub status: Option<u16>,
+    pub success: bool,
+    pub retries: u32,
+    pub error: Option<String>,
+}
+
+/// Collects request results and renders the run summary
+#[derive(Clone, Debug, Default)]
+pub struct RunStats {
+    pub total: u64,
+    pub failed: u64,
+    pub retries: u64,
+    /// Scheduled requests that had to wait for a free worker before being sent
+    pub queued: u64,
+    pub statuses: BTreeMap<u16, u64>,
+    pub endpoints: BTreeMap<&'static str, EndpointStats>,
+    pub errors: BTreeMap<String, ErrorGroup>,
+    /// Service time, from the moment the request was actually sent
+    pub latencies: LatencyHistogram,
+    /// Latency from the intended send time, only recorded for scheduled requests
+    /// to correct for coordinated omission
+    pub response_times: LatencyHistogram,
+    /// Time to load every resource of an issue page, only recorded by page crawls
+    pub page_loads: LatencyHistogram,
+    /// Issue pages with at least one failed request
+    pub failed_pages: u64,
+    pub samples: Option<Vec<Sample>>,
+    /// One entry per configured stage, empty without a load profile
+    pub stages: Vec<StageStats>,
+    /// Requests per second they were sent in, keyed by unix time in seconds.
+    /// Keeps every latency, so it is only collected when a report is written.
+    pub timeline: Option<BTreeMap<u64, TimelineBucket>>,
+}
+
+impl RunStats {
+    /// Empty stats that also collect what the configured reports need
+    pub fn new(config: &Config) -> RunStats {
+        let has_report = config.report.is_some() || config.html_report.is_some();
+        RunStats {
+            samples: config.report_samples.then(Vec::new),
+            timeline: has_report.then(BTreeMap::new),
+            ..RunStats::default()
+        }
+    }
+
+    /// Tracks scheduled requests per stage of the load profile
+    pub fn with_stages(mut self, stages: &[Stage]) -> RunStats {
+        self.stages = stages
+            .iter()
+            .map(|stage| StageStats {
+                duration: stage.duration,
+                target_rps: stage.target_rps,
+                total: 0,
+                failed: 0,
+                queued: 0,
+                latencies: LatencyHistogram::new(),
+                response_times: LatencyHistogram::new(),
+            })
+            .collect();
+        self
+    }
+
+    /// Records a request that may have been scheduled, also counting it in its stage
+    pub fn record_scheduled<T>(&mut self, scheduled: &ScheduledResponse<T>) {
+        let res = &scheduled.res;
+        self.record(res);
+        if let Some(response_time) = scheduled.response_time {
+            self.response_times.record(response_time);
+        }
+
+        let stage = scheduled.stage.and_then(|index| self.stages.get_mut(index));
+        if let Some(stage) = stage {
+            stage.total += 1;
+            if res.data.is_none() {
+                stage.failed += 1;
+            }
+            stage.latencies.record(res.duration);
+            if let Some(response_time) = scheduled.response_time {
+                stage.response_times.record(response_time);
+            }
+        }
+    }
+
+    /// Counts a scheduled request that found every worker busy and waited for one
+    pub fn record_queued(&mut self, stage: Option<usize>) {
+        self.queued += 1;
+        if let Some(stage) = stage.and_then(|index| self.stages.get_mut(index)) {
+            stage.queued += 1;
+        }
+    }
+
+    pub fn record<T>(&mut self, res: &ResponseData<T>) {
+        let failed = res.data.is_none();
+
+        self.total += 1;
+        if failed {
+            self.failed += 1;
+        }
+        self.retries += u64::from(res.retries);
+        if let Some(status) = res.status {
+            *self.statuses.entry(status).or_insert(0) += 1;
+        }
+        self.latencies.record(res.duration);
+
+        if let Some(err) = &res.error {
+            self.record_error(err.group(), 1, || {
+                err.body.clone().unwrap_or_else(|| err.message.clone())
+            });
+        }
+
+        let endpoint = self.endpoints.entry(res.endpoint).or_default();
+        endpoint.total += 1;
+        if failed {
+            endpoint.failed += 1;
+        }
+        if let Some(err) = &res.error {
+            *endpoint.errors.entry(err.group()).or_insert(0) += 1;
+        }
+        endpoint.latencies.record(res.duration);
+
+        if let Some(timeline) = self.timeline.as_mut() {
+            let second = timeline
+                .entry(unix_millis(res.started_at) / 1000)
+                .or_default();
+            second.total += 1;
+            if failed {
+                second.failed += 1;
+            }
+            second
+                .latencies_us
+                .push(u32::try_from(res.duration.as_micros()).unwrap_or(u32::MAX));
+        }
+
+        if let Some(samples) = self.samples.as_mut() {
+            samples.push(Sample {
+                endpoint: res.endpoint.to_string(),
+                timestamp_ms: unix_millis(res.started_at),
+                duration_ms: round_ms(res.duration),
+                status: res.status,
+                success: !failed,
+                retries: res.retries,
+                error: res.error.as_ref().map(|err| err.group()),
+            });
+        }
+    }
+
+    /// Records every request of an issue page and the time the whole page took
+    pub fn record_page(&mut self, page: &IssuePage) {
+        for res in page.responses.iter() {
+            self.record(res);
+        }
+        self.page_loads.record(page.duration);
+        if !page.is_complete() {
+            self.failed_pages += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &RunStats) {
+        self.total += other.total;
+        self.failed += other.failed;
+        self.retries += other.retries;
+        self.queued += other.queued;
+        for (status, count) in other.statuses.iter() {
+            *self.statuses.entry(*status).or_insert(0) += count;
+        }
+        for (name, endpoint) in other.endpoints.iter() {
+            self.endpoints.entry(name).or_default().merge(endpoint);
+        }
+        for (group, errors) in other.errors.iter() {
+            self.record_error(group.clone(), errors.count, || errors.sample.clone());
+        }
+        self.latencies.merge(&other.latencies);
+        self.response_times.merge(&other.response_times);
+        self.page_loads.merge(&other.page_loads);
+        self.failed_pages += other.failed_pages;
+        if let (Some(samples), Some(other_samples)) = (self.samples.as_mut(), &other.samples) {
+            samples.extend(other_samples.iter().cloned());
+        }
+        if let (Some(timeline), Some(other_timeline)) = (self.timeline.as_mut(), &other.timeline) {
+            for (second, bucket) in other_timeline.iter() {
+                timeline.entry(*second).or_default().merge(bucket);
+            }
+        }
+        if self.stages.is_empty() {
+            self.stages = other.stages.clone();
+        } else {
+            for (stage, other_stage) in self.stages.iter_mut().zip(other.stages.iter()) {
+                stage.merge(other_stage);
+            }
+        }
+    }
+
+    fn record_error<F>(&mut self, group: String, count: u64, sample: F)
+    where
+        F: FnOnce() -> String,
+    {
+        self.errors
+            .entry(group)
+            .or_insert_with(|| ErrorGroup {
+                count: 0,
+                sample: sample(),
+            })
+            .count += count;
+    }
+
+    pub fn succeed(&self) -> u64 {
+        self.total - self.failed
+    }
+
+    /// Percentage of successful requests, None when nothing was sent
+    pub fn success_rate(&self) -> Option<BigDecimal> {
+        if self.total == 0 {
+            return None;
+        }
+        let ratio = BigDecimal::from(self.succeed()) / BigDecimal::from(self.total);
+        Some((ratio * BigDecimal::from(100)).round(2))
+    }
+
+    /// Requests per second over the given elapsed time
+    pub fn rps(&self, elapsed: Duration) -> BigDecimal {
+        throughput(self.total, elapsed)
+    }
+
+    /// Prints the summary, `elapsed` being the measured phase and `run_duration` the whole command
+    pub fn print_summary(&self, elapsed: Duration, run_duration: Duration) {
+        let success_rate = match self.success_rate() {
+            Some(rate) => format!("{}%", rate),
+            None => String::from("n/a"),
+        };
+
+        println!();
+        println!("Total requests: {}", self.total);
+        println!("Succeed: {}", self.succeed());
+        println!("Failed: {}", self.failed);
+        println!("Retries: {}", self.retries);
+        if self.queued > 0 {
+            println!("Queued: {}", self.queued);
+        }
+        println!("Success rate: {}", success_rate);
+        if !self.statuses.is_empty() {
+            let statuses: Vec<String> = self
+                .statuses
+                .iter()
+                .map(|(status, count)| format!("{} x {}", status, count))
+                .collect();
+            println!("Status codes: {}", statuses.join(", "));
+        }
+        if !self.response_times.is_empty() {
+            println!("Service time:");
+            self.latencies.print_summary();
+            println!("Response time (from intended send time):");
+            self.response_times.print_summary();
+        } else if !self.latencies.is_empty() {
+            self.latencies.print_summary();
+        }
+        println!("Requests per second: {}", self.rps(elapsed));
+        if !self.page_loads.is_empty() {
+            println!(
+                "Page loads: {} ({} with failed requests)",
+                self.page_loads.len(),
+                self.failed_pages
+            );
+            println!("Page load time:");
+            self.page_loads.print_summary();
+        }
+        println!("Run duration: {} ms", run_duration.as_millis());
+
+        if !self.endpoints.is_empty() {
+            println!();
+            self.print_endpoints(elapsed);
+        }
+
+        if !self.stages.is_empty() {
+            println!();
+            self.print_stages();
+        }
+
+        if !self.errors.is_empty() {
+            println!();
+            self.print_errors();
+        }
+
+        if !self.latencies.is_empty() {
+            println!();
+            self.latencies.print_distribution();
+        }
+    }
+
+    /// Prints failures grouped by status code or failure kind, most frequent first
+    fn print_errors(&self) {
+        let mut errors: Vec<(&String, &ErrorGroup)> = self.errors.iter().collect();
+        errors.sort_by_key(|(_, errors)| Reverse(errors.count));
+
+        println!("Errors:");
+        for (group, errors) in errors {
+            println!("  {} x {}  ({})", group, errors.count, errors.sample);
+        }
+    }
+
+    /// Prints a table per load profile stage to locate where latency starts to climb
+    fn print_stages(&self) {
+        println!(
+            "{:<6} {:>9} {:>10} {:>8} {:>7} {:>8} {:>10} {:>10} {:>10} {:>12} {:>10}",
+            "Stage",
+            "Duration",
+            "Target/s",
+            "Count",
+            "Errors",
+            "Queued",
+            "p50 ms",
+            "p95 ms",
+            "p99 ms",
+            "Resp p99 ms",
+            "Req/s"
+        );
+        for (index, stage) in self.stages.iter().enumerate() {
+            let percentiles: Vec<String> = [50.0, 95.0, 99.0]
+                .iter()
+                .map(|p| format!("{:.3}", as_ms(stage.latencies.percentile(*p))))
+                .collect();
+            println!(
+                "{:<6} {:>9} {:>10} {:>8} {:>7} {:>8} {:>10} {:>10} {:>10} {:>12.3} {:>10}",
+                index + 1,
+                humantime::format_duration(stage.duration).to_string(),
+                stage.target_rps,
+                stage.total,
+                stage.failed,
+                stage.queued,
+                percentiles[0],
+                percentiles[1],
+                percentiles[2],
+                as_ms(stage.response_times.percentile(99.0)),
+                throughput(stage.total, stage.duration)
+            );
+        }
+    }
+
+    /// Prints a table of counts, errors, percentiles and throughput per endpoint
+    fn print_endpoints(&self, elapsed: Duration) {
+        println!(
+            "{:<18} {:>8} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "Endpoint", "Count", "Errors", "p50 ms", "p90 ms", "p95 ms", "p99 ms", "Req/s"
+        );
+        for (name, endpoint) in self.endpoints.iter() {
+            let percentiles: Vec<String> = [50.0, 90.0, 95.0, 99.0]
+                .iter()
+                .map(|p| format!("{:.3}", as_ms(endpoint.latencies.percentile(*p))))
+                .collect();
+            println!(
+                "{:<18} {:>8} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                name,
+                endpoint.total,
+                endpoint.failed,
+                percentiles[0],
+                percentiles[1],
+                percentiles[2],
+                percentiles[3],
+                throughput(endpoint.total, elapsed)
+            );
+        }
+    }
+}
+
+/// Stats updated by request tasks while the run is in progress, shared by every clone of the client
+#[derive(Clone, Debug, Default)]
+pub struct SharedStats {
+    stats: Arc<Mutex<RunStats>>,
+}
+
+impl SharedStats {
+    pub fn record<T>(&self, res: &ResponseData<T>) {
+        self.lock().record(res);
+    }
+
+    /// Returns the stats collected so far and starts over
+    pub fn take(&self) -> RunStats {
+        std::mem::take(&mut *self.lock())
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, RunStats> {
+        self.stats.lock().unwrap()
+    }
+}
+
+/// Requests per second over the given elapsed time, rounded to 2 decimals
+pub fn throughput(count: u64, elapsed: Duration) -> BigDecimal {
+    let millis = elapsed.as_millis();
+    if millis == 0 {
+        return BigDecimal::from(0);
+    }
+    let rps = BigDecimal::from(count) * BigDecimal::from(1000) / BigDecimal::from(millis);
+    rps.round(2)
+}
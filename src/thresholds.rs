@@ -0,0 +1,169 @@
+use bigdecimal::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::crawler::ENDPOINT_NAMES;
+use crate::stats::{as_ms, throughput, LatencyHistogram, RunStats};
+
+/// Limits applied to the whole run or to a single endpoint
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ThresholdLimits {
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    /// Maximum percentage of failed requests, between 0 and 100
+    pub max_error_rate: Option<f64>,
+    pub min_rps: Option<f64>,
+}
+
+/// SLO thresholds configured via the `[thresholds]` section
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Thresholds {
+    #[serde(flatten)]
+    pub limits: ThresholdLimits,
+    #[serde(default)]
+    pub endpoints: BTreeMap<String, ThresholdLimits>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ThresholdCheck {
+    /// Endpoint name or "total" for the whole run
+    pub scope: String,
+    pub metric: &'static str,
+    pub limit: f64,
+    pub actual: f64,
+    pub passed: bool,
+    /// False when the command never hit the endpoint, the check is then skipped
+    pub exercised: bool,
+}
+
+impl ThresholdLimits {
+    fn validate(&self) -> Result<(), &'static str> {
+        if let Some(rate) = self.max_error_rate {
+            if !(0.0..=100.0).contains(&rate) {
+                return Err("Threshold max error rate must be between 0 to 100");
+            }
+        }
+        Ok(())
+    }
+
+    fn check(
+        &self,
+        scope: &str,
+        total: u64,
+        failed: u64,
+        latencies: &LatencyHistogram,
+        elapsed: Duration,
+    ) -> Vec<ThresholdCheck> {
+        let mut checks: Vec<ThresholdCheck> = Vec::new();
+        let mut push = |metric: &'static str, limit: f64, actual: f64, passed: bool| {
+            checks.push(ThresholdCheck {
+                scope: scope.to_string(),
+                metric,
+                limit,
+                actual,
+                passed,
+                exercised: true,
+            });
+        };
+
+        let percentiles = [
+            ("p50_ms", self.p50_ms, 50.0),
+            ("p90_ms", self.p90_ms, 90.0),
+            ("p95_ms", self.p95_ms, 95.0),
+            ("p99_ms", self.p99_ms, 99.0),
+        ];
+        for (metric, limit, percentile) in percentiles {
+            if let Some(limit) = limit {
+                let actual = as_ms(latencies.percentile(percentile));
+                push(metric, limit, actual, actual <= limit);
+            }
+        }
+
+        if let Some(limit) = self.max_error_rate {
+            let actual = if total == 0 {
+                0.0
+            } else {
+                failed as f64 * 100.0 / total as f64
+            };
+            push("max_error_rate", limit, actual, actual <= limit);
+        }
+
+        if let Some(limit) = self.min_rps {
+            let actual = throughput(total, elapsed).to_f64().unwrap_or(0.0);
+            push("min_rps", limit, actual, actual >= limit);
+        }
+
+        checks
+    }
+}
+
+impl Thresholds {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        self.limits.validate()?;
+        for (name, limits) in self.endpoints.iter() {
+            if !ENDPOINT_NAMES.contains(&name.as_str()) {
+                return Err("Threshold endpoint name is unknown");
+            }
+            limits.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Checks every configured threshold against the final stats
+    pub fn evaluate(&self, stats: &RunStats, elapsed: Duration) -> Vec<ThresholdCheck> {
+        let mut checks = self.limits.check(
+            "total",
+            stats.total,
+            stats.failed,
+            &stats.latencies,
+            elapsed,
+        );
+
+        for (name, limits) in self.endpoints.iter() {
+            match stats.endpoints.get(name.as_str()) {
+                Some(endpoint) => checks.extend(limits.check(
+                    name,
+                    endpoint.total,
+                    endpoint.failed,
+                    &endpoint.latencies,
+                    elapsed,
+                )),
+                // Not every command hits every endpoint, ie: crawls never create issues
+                None => checks.push(ThresholdCheck {
+                    scope: name.clone(),
+                    metric: "count",
+                    limit: 1.0,
+                    actual: 0.0,
+                    passed: true,
+                    exercised: false,
+                }),
+            }
+        }
+
+        checks
+    }
+}
+
+pub fn print_checks(checks: &[ThresholdCheck]) {
+    println!();
+    println!("Thresholds:");
+    for check in checks {
+        if !check.exercised {
+            println!("  SKIP {}: not exercised", check.scope);
+            continue;
+        }
+        let (status, operator) = match (check.passed, check.metric) {
+            (true, "min_rps") => ("PASS", ">="),
+            (false, "min_rps") => ("FAIL", "<"),
+            (true, _) => ("PASS", "<="),
+            (false, _) => ("FAIL", ">"),
+        };
+        println!(
+            "  {} {} {}: {:.3} {} {}",
+            status, check.scope, check.metric, check.actual, operator, check.limit
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::config::Config;
+use crate::crawler::ApiClient;
+use crate::error::Result;
+use crate::report::{to_f64, EndpointReport, LatencyReport};
+use crate::stats::{round_ms, RunStats};
+
+/// One line of the time-series file
+#[derive(Serialize)]
+struct Snapshot {
+    timestamp: String,
+    /// Time since the start of the run at the end of the interval
+    elapsed_ms: f64,
+    interval_ms: f64,
+    total: u64,
+    failed: u64,
+    retries: u64,
+    rps: f64,
+    latency: LatencyReport,
+    statuses: BTreeMap<u16, u64>,
+    endpoints: Vec<EndpointReport>,
+}
+
+/// Writes a snapshot of the last interval to a JSONL file until finished
+pub struct TimeSeriesWriter {
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<Result<()>>,
+}
+
+impl TimeSeriesWriter {
+    /// Starts writing snapshots when a time-series file is configured
+    pub fn start(config: &Config, client: &ApiClient) -> Result<Option<TimeSeriesWriter>> {
+        let (Some(filename), Some(interval_stats)) =
+            (&config.timeseries_file, client.interval_stats())
+        else {
+            return Ok(None);
+        };
+
+        let mut file = BufWriter::new(File::create(filename)?);
+        let interval = config.timeseries_interval;
+        let interval_stats = interval_stats.clone();
+        let (stop, mut stopped) = oneshot::channel();
+
+        // Discard whatever was recorded while preparing the run
+        interval_stats.take();
+
+        let handle = tokio::spawn(async move {
+            let started = Instant::now();
+            let mut last = started;
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+
+            loop {
+                let finished = tokio::select! {
+                    _ = ticker.tick() => false,
+                    _ = &mut stopped => true,
+                };
+
+                let now = Instant::now();
+                let stats = interval_stats.take();
+                // Skip an empty trailing interval
+                if !finished || stats.total > 0 {
+                    let snapshot = Snapshot::new(&stats, now - started, now - last);
+                    serde_json::to_writer(&mut file, &snapshot)?;
+                    file.write_all(b"\n")?;
+                    file.flush()?;
+                }
+                last = now;
+
+                if finished {
+                    return Ok(());
+                }
+            }
+        });
+
+        Ok(Some(TimeSeriesWriter { stop, handle }))
+    }
+
+    /// Writes the last partial interval and closes the file
+    pub async fn finish(self) -> Result<()> {
+        let _ = self.stop.send(());
+        self.handle.await?
+    }
+}
+
+impl Snapshot {
+    fn new(stats: &RunStats, elapsed: Duration, interval: Duration) -> Snapshot {
+        Snapshot {
+            timestamp: humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+            elapsed_ms: round_ms(elapsed),
+            interval_ms: round_ms(interval),
+            total: stats.total,
+            failed: stats.failed,
+            retries: stats.retries,
+            rps: to_f64(stats.rps(interval)),
+            latency: LatencyReport::from(&stats.latencies),
+            statuses: stats.statuses.clone(),
+            endpoints: EndpointReport::from_stats(stats, interval),
+        }
+    }
+}